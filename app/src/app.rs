@@ -2,21 +2,33 @@ use leptos::*;
 use leptos_meta::provide_meta_context;
 use leptos_router::{Route, Router, Routes};
 
+use crate::appearance::provide_appearance;
+use crate::gas::provide_cost_model;
+use crate::settings::provide_settings;
 use crate::navigation::Navigation;
 use crate::home::Home;
 use crate::disassembler::Disassembler;
+use crate::emulator::Emulator;
+use crate::conformance::Conformance;
+use crate::compare::Compare;
 
 #[component]
 pub fn App() -> impl IntoView {
     provide_meta_context();
+    provide_appearance();
+    provide_settings();
+    provide_cost_model();
 
     view! {
-        <body class="flex flex-col min-h-screen">
+        <body class="flex flex-col min-h-screen bg-white text-gray-900 dark:bg-gray-900 dark:text-gray-100">
             <Navigation/>
             <Router>
                 <Routes>
                     <Route path="" view=move || view! { <Home/> }/>
                     <Route path="disassembler" view=move || view! { <Disassembler/> }/>
+                    <Route path="emulator" view=move || view! { <Emulator/> }/>
+                    <Route path="conformance" view=move || view! { <Conformance/> }/>
+                    <Route path="compare" view=move || view! { <Compare/> }/>
                 </Routes>
             </Router>
         </body>
@@ -0,0 +1,116 @@
+use leptos::*;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::{closure::Closure, JsCast};
+use crate::storage;
+
+const STORAGE_KEY: &str = "polkarun.appearance";
+const DARK_SCHEME_QUERY: &str = "(prefers-color-scheme: dark)";
+
+/// Zoom steps in percent, the same ones browsers use.
+const ZOOM_LEVELS: &[u16] = &[50, 67, 75, 80, 90, 100, 110, 125, 150, 175, 200];
+const DEFAULT_ZOOM: u16 = 100;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Theme {
+    /// Follows `prefers-color-scheme`
+    #[default]
+    System,
+    Day,
+    Dark,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Appearance {
+    pub theme: Theme,
+    /// Scale of the hex dump and disassembly listing, in percent
+    pub zoom: u16,
+}
+
+impl Default for Appearance {
+    fn default() -> Self {
+        Self {
+            theme: Theme::default(),
+            zoom: DEFAULT_ZOOM,
+        }
+    }
+}
+
+impl Appearance {
+    pub fn zoom_in(&mut self) {
+        if let Some(&zoom) = ZOOM_LEVELS.iter().find(|&&zoom| zoom > self.zoom) {
+            self.zoom = zoom;
+        }
+    }
+
+    pub fn zoom_out(&mut self) {
+        if let Some(&zoom) = ZOOM_LEVELS.iter().rev().find(|&&zoom| zoom < self.zoom) {
+            self.zoom = zoom;
+        }
+    }
+
+    pub fn reset_zoom(&mut self) {
+        self.zoom = DEFAULT_ZOOM;
+    }
+}
+
+fn system_prefers_dark() -> Option<web_sys::MediaQueryList> {
+    window().match_media(DARK_SCHEME_QUERY).ok()?
+}
+
+// Shortcuts are plain keys, so they are ignored while typing into a field
+fn is_editing(event: &web_sys::KeyboardEvent) -> bool {
+    event
+        .target()
+        .and_then(|target| target.dyn_into::<web_sys::Element>().ok())
+        .map_or(false, |element| matches!(element.tag_name().as_str(), "INPUT" | "TEXTAREA" | "SELECT"))
+}
+
+/// Loads the stored appearance, keeps the `dark` class of the document in
+/// sync with it and installs the zoom shortcuts: `+` zooms in, `-` zooms out
+/// and `0` goes back to the default size.
+pub fn provide_appearance() {
+    let appearance = create_rw_signal(storage::load::<Appearance>(STORAGE_KEY).unwrap_or_default());
+
+    let media_query = system_prefers_dark();
+    let (system_dark, set_system_dark) = create_signal(media_query.as_ref().map_or(false, |query| query.matches()));
+    if let Some(query) = media_query {
+        let query_c = query.clone();
+        let on_change = Closure::wrap(Box::new(move || set_system_dark(query_c.matches())) as Box<dyn Fn()>);
+        query.set_onchange(Some(on_change.as_ref().unchecked_ref()));
+        on_change.forget();
+    }
+
+    create_effect(move |_| {
+        let appearance = appearance();
+        storage::save(STORAGE_KEY, &appearance);
+
+        let dark = match appearance.theme {
+            Theme::System => system_dark(),
+            Theme::Day => false,
+            Theme::Dark => true,
+        };
+        if let Some(root) = document().document_element() {
+            if root.class_list().toggle_with_force("dark", dark).is_err() {
+                log::warn!("Failed to switch the color scheme");
+            }
+        }
+    });
+
+    let _ = window_event_listener(ev::keydown, move |event| {
+        if event.ctrl_key() || event.meta_key() || event.alt_key() || is_editing(&event) {
+            return;
+        }
+        match event.key().as_str() {
+            "+" | "=" => appearance.update(Appearance::zoom_in),
+            "-" => appearance.update(Appearance::zoom_out),
+            "0" => appearance.update(Appearance::reset_zoom),
+            _ => {}
+        }
+    });
+
+    provide_context(appearance);
+}
+
+pub fn use_appearance() -> RwSignal<Appearance> {
+    expect_context()
+}
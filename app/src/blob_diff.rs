@@ -0,0 +1,542 @@
+use crate::analysis::{analyze_program, Function, ProgramAnalysis};
+use crate::blob_structure::blob_sections;
+use polkavm_common::program::ProgramBlob;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+// Past this many cells the alignment table costs more than it's worth and
+// the remaining middle of a function is shown as changed wholesale
+const MAX_ALIGNMENT_CELLS: usize = 4_000_000;
+
+/// Unnamed functions less alike than this are not paired up
+const MIN_SIMILARITY: f64 = 0.5;
+
+/// Unnamed functions are only compared with the ones within this many
+/// places of the same relative position in the other blob, which keeps
+/// pairing linear in the number of functions
+const PAIRING_WINDOW: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineChange {
+    Same,
+    Inserted,
+    Removed,
+    Changed,
+}
+
+/// One row of an aligned listing; a side is `None` where it has no
+/// counterpart.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiffLine {
+    pub change: LineChange,
+    pub left: Option<DiffInstruction>,
+    pub right: Option<DiffInstruction>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiffInstruction {
+    /// Byte offset in its blob
+    pub offset: usize,
+    pub assembly: String,
+    /// `assembly` without jump targets, which is what gets compared
+    normalized: String,
+    /// Jump target of the block this instruction starts, if it starts one
+    block: Option<u32>,
+}
+
+impl DiffInstruction {
+    fn new(offset: usize, assembly: String) -> Self {
+        DiffInstruction {
+            normalized: normalize_targets(&assembly),
+            offset,
+            assembly,
+            block: None,
+        }
+    }
+}
+
+// Jump targets are block numbers, which all shift when a block is inserted
+// anywhere before them, so `@1f` and `@20` compare equal
+fn normalize_targets(assembly: &str) -> String {
+    let mut normalized = String::with_capacity(assembly.len());
+    let mut in_target = false;
+    for character in assembly.chars() {
+        if in_target && character.is_ascii_hexdigit() {
+            continue;
+        }
+        in_target = character == '@';
+        normalized.push(character);
+    }
+    normalized
+}
+
+fn jump_targets(assembly: &str) -> Vec<u32> {
+    assembly
+        .split('@')
+        .skip(1)
+        .filter_map(|rest| {
+            let digits = rest.len() - rest.trim_start_matches(|character: char| character.is_ascii_hexdigit()).len();
+            u32::from_str_radix(&rest[..digits], 16).ok()
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FunctionChange {
+    Unchanged,
+    Modified,
+    Added,
+    Removed,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FunctionDiff {
+    pub name: String,
+    pub change: FunctionChange,
+    pub lines: Vec<DiffLine>,
+}
+
+impl FunctionDiff {
+    pub fn count(&self, change: LineChange) -> usize {
+        self.lines.iter().filter(|line| line.change == change).count()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SectionDelta {
+    pub name: String,
+    pub left: usize,
+    pub right: usize,
+}
+
+impl SectionDelta {
+    pub fn delta(&self) -> i64 {
+        self.right as i64 - self.left as i64
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BlobDiff {
+    pub sections: Vec<SectionDelta>,
+    pub functions: Vec<FunctionDiff>,
+}
+
+fn function_instructions(analysis: &ProgramAnalysis, function: &Function) -> Vec<DiffInstruction> {
+    function
+        .blocks
+        .iter()
+        .filter_map(|&jump_target| analysis.block(jump_target))
+        .flat_map(|block| {
+            let instructions = analysis.instructions.get(block.instructions.clone()).unwrap_or_default();
+            instructions.iter().enumerate().map(move |(index, analyzed)| DiffInstruction {
+                block: (index == 0).then_some(block.jump_target),
+                ..DiffInstruction::new(analysis.code_offset + analyzed.offset, analyzed.instruction.to_string())
+            })
+        })
+        .collect()
+}
+
+/// Marks lines as changed whose jump targets don't follow the renumbering
+/// of blocks. Blocks correspond where the aligned listings start them on the
+/// same line, so `jump @3` becoming `jump @4` stays the same when block 3
+/// turned into block 4, and is a change otherwise, as is `jump @3` staying
+/// `jump @3`. Targets without a counterpart must be equal.
+fn flag_retargeted(functions: &mut [FunctionDiff]) {
+    let mut blocks = HashMap::new();
+    for line in functions.iter().flat_map(|function| &function.lines) {
+        if let (Some(left), Some(right)) = (&line.left, &line.right) {
+            if let (Some(left_block), Some(right_block)) = (left.block, right.block) {
+                blocks.insert(left_block, right_block);
+            }
+        }
+    }
+
+    for function in functions {
+        for line in &mut function.lines {
+            let (Some(left), Some(right)) = (&line.left, &line.right) else {
+                continue;
+            };
+            if line.change != LineChange::Same {
+                continue;
+            }
+            let followed = jump_targets(&left.assembly)
+                .into_iter()
+                .zip(jump_targets(&right.assembly))
+                .all(|(left_target, right_target)| *blocks.get(&left_target).unwrap_or(&left_target) == right_target);
+            if !followed {
+                line.change = LineChange::Changed;
+            }
+        }
+        if function.change == FunctionChange::Unchanged && function.lines.iter().any(|line| line.change != LineChange::Same) {
+            function.change = FunctionChange::Modified;
+        }
+    }
+}
+
+/// Aligns two instruction sequences by their longest common subsequence,
+/// pairing up removals directly followed by insertions as changes.
+fn align(left: &[DiffInstruction], right: &[DiffInstruction]) -> Vec<DiffLine> {
+    let same = |a: &DiffInstruction, b: &DiffInstruction| a.normalized == b.normalized;
+    let prefix = left.iter().zip(right).take_while(|(a, b)| same(a, b)).count();
+    let suffix = left[prefix..]
+        .iter()
+        .rev()
+        .zip(right[prefix..].iter().rev())
+        .take_while(|(a, b)| same(a, b))
+        .count();
+    let (middle_left, middle_right) = (&left[prefix..left.len() - suffix], &right[prefix..right.len() - suffix]);
+
+    let mut lines = left[..prefix]
+        .iter()
+        .zip(&right[..prefix])
+        .map(|(a, b)| DiffLine {
+            change: LineChange::Same,
+            left: Some(a.clone()),
+            right: Some(b.clone()),
+        })
+        .collect::<Vec<_>>();
+
+    // Edit script over the middle as (left index, right index) steps
+    let (n, m) = (middle_left.len(), middle_right.len());
+    let mut script = Vec::new();
+    if (n + 1) * (m + 1) <= MAX_ALIGNMENT_CELLS {
+        let mut lengths = vec![vec![0u32; m + 1]; n + 1];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lengths[i][j] = if same(&middle_left[i], &middle_right[j]) {
+                    lengths[i + 1][j + 1] + 1
+                } else {
+                    lengths[i + 1][j].max(lengths[i][j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < n || j < m {
+            if i < n && j < m && same(&middle_left[i], &middle_right[j]) {
+                script.push((Some(i), Some(j)));
+                i += 1;
+                j += 1;
+            } else if j == m || (i < n && lengths[i + 1][j] >= lengths[i][j + 1]) {
+                script.push((Some(i), None));
+                i += 1;
+            } else {
+                script.push((None, Some(j)));
+                j += 1;
+            }
+        }
+    } else {
+        script.extend((0..n).map(|i| (Some(i), None)));
+        script.extend((0..m).map(|j| (None, Some(j))));
+    }
+
+    let mut removed = Vec::new();
+    let mut inserted = Vec::new();
+    let flush = |removed: &mut Vec<&DiffInstruction>, inserted: &mut Vec<&DiffInstruction>, lines: &mut Vec<DiffLine>| {
+        let pairs = removed.len().max(inserted.len());
+        for index in 0..pairs {
+            let left = removed.get(index).map(|&instruction| instruction.clone());
+            let right = inserted.get(index).map(|&instruction| instruction.clone());
+            let change = match (&left, &right) {
+                (Some(_), Some(_)) => LineChange::Changed,
+                (Some(_), None) => LineChange::Removed,
+                _ => LineChange::Inserted,
+            };
+            lines.push(DiffLine { change, left, right });
+        }
+        removed.clear();
+        inserted.clear();
+    };
+    for step in script {
+        match step {
+            (Some(i), Some(j)) => {
+                flush(&mut removed, &mut inserted, &mut lines);
+                lines.push(DiffLine {
+                    change: LineChange::Same,
+                    left: Some(middle_left[i].clone()),
+                    right: Some(middle_right[j].clone()),
+                });
+            }
+            (Some(i), None) => removed.push(&middle_left[i]),
+            (None, Some(j)) => inserted.push(&middle_right[j]),
+            (None, None) => {}
+        }
+    }
+    flush(&mut removed, &mut inserted, &mut lines);
+
+    lines.extend(
+        left[left.len() - suffix..]
+            .iter()
+            .zip(&right[right.len() - suffix..])
+            .map(|(a, b)| DiffLine {
+                change: LineChange::Same,
+                left: Some(a.clone()),
+                right: Some(b.clone()),
+            }),
+    );
+    lines
+}
+
+fn section_deltas(left: &[u8], right: &[u8]) -> Result<Vec<SectionDelta>, &'static str> {
+    let mut sizes = BTreeMap::<String, (usize, usize)>::new();
+    for section in blob_sections(left)? {
+        sizes.entry(section.name).or_default().0 += section.range.len();
+    }
+    for section in blob_sections(right)? {
+        sizes.entry(section.name).or_default().1 += section.range.len();
+    }
+
+    let mut deltas = sizes
+        .into_iter()
+        .map(|(name, (left, right))| SectionDelta { name, left, right })
+        .collect::<Vec<_>>();
+    deltas.push(SectionDelta {
+        name: "Total".to_string(),
+        left: left.len(),
+        right: right.len(),
+    });
+    Ok(deltas)
+}
+
+// How often each normalized instruction occurs in a function, built once per
+// function for pairing
+struct InstructionCounts<'a> {
+    counts: HashMap<&'a str, usize>,
+    total: usize,
+}
+
+impl<'a> InstructionCounts<'a> {
+    fn new(instructions: &'a [DiffInstruction]) -> Self {
+        let mut counts = HashMap::new();
+        for instruction in instructions {
+            *counts.entry(instruction.normalized.as_str()).or_default() += 1;
+        }
+        InstructionCounts {
+            counts,
+            total: instructions.len(),
+        }
+    }
+
+    // Whether the sizes alone allow `similarity` to reach `MIN_SIMILARITY`
+    fn may_match(&self, other: &Self) -> bool {
+        let total = self.total + other.total;
+        total == 0 || 2.0 * self.total.min(other.total) as f64 / total as f64 >= MIN_SIMILARITY
+    }
+
+    // Share of instructions the two functions have in common, ignoring order
+    fn similarity(&self, other: &Self) -> f64 {
+        if self.total == 0 && other.total == 0 {
+            return 1.0;
+        }
+        let (fewer, more) = if self.counts.len() <= other.counts.len() { (self, other) } else { (other, self) };
+        let common = fewer
+            .counts
+            .iter()
+            .map(|(instruction, &count)| count.min(more.counts.get(instruction).copied().unwrap_or(0)))
+            .sum::<usize>();
+        2.0 * common as f64 / (self.total + other.total) as f64
+    }
+}
+
+/// Pairs up unnamed functions, given in address order. Each left function
+/// takes the most similar right one still unpaired among those about as far
+/// into their blob, preferring the one closest to that position on ties;
+/// functions without a similar enough counterpart are left unpaired.
+fn pair_unnamed(left: &[Vec<DiffInstruction>], right: &[Vec<DiffInstruction>]) -> Vec<(Option<usize>, Option<usize>)> {
+    let left_counts = left.iter().map(|instructions| InstructionCounts::new(instructions)).collect::<Vec<_>>();
+    let right_counts = right.iter().map(|instructions| InstructionCounts::new(instructions)).collect::<Vec<_>>();
+
+    let mut unpaired = (0..right.len()).collect::<BTreeSet<_>>();
+    let mut pairs = Vec::new();
+    for (i, counts) in left_counts.iter().enumerate() {
+        let expected = i * right.len() / left.len();
+        let best = unpaired
+            .range(expected.saturating_sub(PAIRING_WINDOW)..=expected + PAIRING_WINDOW)
+            .filter(|&&j| counts.may_match(&right_counts[j]))
+            .map(|&j| (j, counts.similarity(&right_counts[j])))
+            .filter(|&(_, similarity)| similarity >= MIN_SIMILARITY)
+            .max_by(|(a, a_similarity), (b, b_similarity)| {
+                a_similarity
+                    .total_cmp(b_similarity)
+                    .then_with(|| b.abs_diff(expected).cmp(&a.abs_diff(expected)))
+            })
+            .map(|(j, _)| j);
+        if let Some(j) = best {
+            unpaired.remove(&j);
+        }
+        pairs.push((Some(i), best));
+    }
+    pairs.extend(unpaired.into_iter().map(|j| (None, Some(j))));
+    pairs
+}
+
+fn function_diff(name: String, left: Option<Vec<DiffInstruction>>, right: Option<Vec<DiffInstruction>>) -> FunctionDiff {
+    let lines = align(left.as_deref().unwrap_or_default(), right.as_deref().unwrap_or_default());
+    let change = match (&left, &right) {
+        (Some(_), None) => FunctionChange::Removed,
+        (None, Some(_)) => FunctionChange::Added,
+        _ if lines.iter().all(|line| line.change == LineChange::Same) => FunctionChange::Unchanged,
+        _ => FunctionChange::Modified,
+    };
+    FunctionDiff { name, change, lines }
+}
+
+/// Function-by-function diff of two blobs. Exports are paired by name and
+/// the remaining functions by how alike their instructions are, since their
+/// `sub_N` names change whenever blocks are inserted before them.
+pub fn compare_blobs(left: &[u8], right: &[u8]) -> Result<BlobDiff, &'static str> {
+    let left_blob = ProgramBlob::parse(left).map_err(|_| "Failed to parse the left blob")?;
+    let right_blob = ProgramBlob::parse(right).map_err(|_| "Failed to parse the right blob")?;
+    let left_analysis = analyze_program(&left_blob)?;
+    let right_analysis = analyze_program(&right_blob)?;
+
+    let export_names = |blob: &ProgramBlob| {
+        blob.exports()
+            .filter_map(Result::ok)
+            .map(|export| export.symbol().to_string())
+            .collect::<BTreeSet<_>>()
+    };
+    let left_exports = export_names(&left_blob);
+    let right_exports = export_names(&right_blob);
+
+    let mut exports = BTreeMap::<String, (Option<Vec<DiffInstruction>>, Option<Vec<DiffInstruction>>)>::new();
+    let (mut left_names, mut left_unnamed) = (Vec::new(), Vec::new());
+    let (mut right_names, mut right_unnamed) = (Vec::new(), Vec::new());
+    for function in &left_analysis.functions {
+        let instructions = function_instructions(&left_analysis, function);
+        if left_exports.contains(&function.name) {
+            exports.entry(function.name.clone()).or_default().0 = Some(instructions);
+        } else {
+            left_names.push(function.name.clone());
+            left_unnamed.push(instructions);
+        }
+    }
+    for function in &right_analysis.functions {
+        let instructions = function_instructions(&right_analysis, function);
+        if right_exports.contains(&function.name) {
+            exports.entry(function.name.clone()).or_default().1 = Some(instructions);
+        } else {
+            right_names.push(function.name.clone());
+            right_unnamed.push(instructions);
+        }
+    }
+
+    let mut functions = exports
+        .into_iter()
+        .map(|(name, (left_instructions, right_instructions))| function_diff(name, left_instructions, right_instructions))
+        .collect::<Vec<_>>();
+    for (i, j) in pair_unnamed(&left_unnamed, &right_unnamed) {
+        let name = match (i, j) {
+            (Some(i), Some(j)) if left_names[i] != right_names[j] => format!("{} → {}", left_names[i], right_names[j]),
+            (Some(i), _) => left_names[i].clone(),
+            (None, Some(j)) => right_names[j].clone(),
+            (None, None) => continue,
+        };
+        functions.push(function_diff(
+            name,
+            i.map(|i| left_unnamed[i].clone()),
+            j.map(|j| right_unnamed[j].clone()),
+        ));
+    }
+    flag_retargeted(&mut functions);
+
+    Ok(BlobDiff {
+        sections: section_deltas(left, right)?,
+        functions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn function(assembly: &[&str]) -> Vec<DiffInstruction> {
+        assembly
+            .iter()
+            .enumerate()
+            .map(|(offset, assembly)| DiffInstruction::new(offset, assembly.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn shifted_jump_targets_compare_equal() {
+        assert_eq!(normalize_targets("if a0 == 0: jump @1f"), "if a0 == 0: jump @");
+        let lines = align(&function(&["a0 = a0 + 1", "jump @3"]), &function(&["a0 = a0 + 1", "jump @4"]));
+        assert!(lines.iter().all(|line| line.change == LineChange::Same));
+    }
+
+    #[test]
+    fn retargeted_jumps_are_changed() {
+        let block_start = |offset: usize, assembly: &str, block: u32| DiffInstruction {
+            block: Some(block),
+            ..DiffInstruction::new(offset, assembly.to_string())
+        };
+        // Block 3 became block 4 and block 7 became block 8; the first jump
+        // follows its block along, the second now goes somewhere else
+        let left = vec![
+            block_start(0, "jump @3", 2),
+            block_start(1, "a0 = 1", 3),
+            DiffInstruction::new(2, "jump @7".to_string()),
+            block_start(3, "a0 = 2", 7),
+        ];
+        let right = vec![
+            block_start(0, "jump @4", 3),
+            block_start(1, "a0 = 1", 4),
+            DiffInstruction::new(2, "jump @4".to_string()),
+            block_start(3, "a0 = 2", 8),
+        ];
+        let mut functions = vec![function_diff("main".to_string(), Some(left), Some(right))];
+        assert_eq!(functions[0].change, FunctionChange::Unchanged);
+
+        flag_retargeted(&mut functions);
+        let changes = functions[0].lines.iter().map(|line| line.change).collect::<Vec<_>>();
+        assert_eq!(changes, [LineChange::Same, LineChange::Same, LineChange::Changed, LineChange::Same]);
+        assert_eq!(functions[0].change, FunctionChange::Modified);
+        assert_eq!(jump_targets("if a0 == 0: jump @1f"), [0x1f]);
+    }
+
+    #[test]
+    fn unchanged_text_can_still_be_retargeted() {
+        let block_start = |offset: usize, assembly: &str, block: u32| DiffInstruction {
+            block: Some(block),
+            ..DiffInstruction::new(offset, assembly.to_string())
+        };
+        // Block 3 became block 4, but the jump still goes to @3, which is
+        // now what used to be block 2; the call into unmapped @9 is unchanged
+        let left = vec![block_start(0, "jump @3", 2), block_start(1, "call @9", 3)];
+        let right = vec![block_start(0, "jump @3", 3), block_start(1, "call @9", 4)];
+        let mut functions = vec![function_diff("main".to_string(), Some(left), Some(right))];
+
+        flag_retargeted(&mut functions);
+        let changes = functions[0].lines.iter().map(|line| line.change).collect::<Vec<_>>();
+        assert_eq!(changes, [LineChange::Changed, LineChange::Same]);
+        assert_eq!(functions[0].change, FunctionChange::Modified);
+    }
+
+    #[test]
+    fn pairs_unnamed_functions_by_similarity() {
+        let left = [function(&["a0 = 1", "ret"]), function(&["a1 = a0 + a2", "a1 = a1 * a2", "ret"])];
+        let right = [
+            function(&["a1 = a0 + a2", "a1 = a1 * a2", "ret"]),
+            function(&["trap"]),
+            function(&["a0 = 1", "jump @2", "ret"]),
+        ];
+        assert_eq!(pair_unnamed(&left, &right), [(Some(0), Some(2)), (Some(1), Some(0)), (None, Some(1))]);
+        assert_eq!(pair_unnamed(&left, &[]), [(Some(0), None), (Some(1), None)]);
+    }
+
+    #[test]
+    fn pairs_thousands_of_shifted_functions() {
+        let functions = (0..5000)
+            .map(|index| {
+                let constant = format!("a0 = {}", index);
+                function(&[constant.as_str(), "a1 = a0 + a2", "ret"])
+            })
+            .collect::<Vec<_>>();
+        let mut right = vec![function(&["trap"])];
+        right.extend(functions.iter().cloned());
+
+        let pairs = pair_unnamed(&functions, &right);
+        assert_eq!(pairs.len(), right.len());
+        assert!(pairs[..functions.len()].iter().enumerate().all(|(i, &pair)| pair == (Some(i), Some(i + 1))));
+        assert_eq!(pairs[functions.len()], (None, Some(0)));
+    }
+}
@@ -0,0 +1,58 @@
+use leptos::*;
+pub use polkarun_core::sections::{blob_sections, subslice_range, BlobSection};
+use crate::settings::use_settings;
+
+#[component]
+pub fn BlobStructure<F: Fn(BlobSection) + 'static>(
+    sections: ReadSignal<Vec<BlobSection>>,
+    on_section_selected: F,
+) -> impl IntoView {
+    let on_section_selected = std::rc::Rc::new(on_section_selected);
+    let (expanded, set_expanded) = create_signal(None::<String>);
+    let settings = use_settings();
+
+    view! {
+        <div class="w-full h-full overflow-auto border border-gray-200 rounded bg-gray-100 text-xs dark:bg-gray-800 dark:border-gray-700">
+            <div class="p-2 font-bold bg-gray-200 dark:bg-gray-700">"Structure"</div>
+            <For
+                each=move || sections().into_iter()
+                key=|section| (section.name.clone(), section.range.start)
+                children=move |section| {
+                    let on_section_selected = on_section_selected.clone();
+                    let name = section.name.clone();
+                    let is_expanded = {
+                        let name = name.clone();
+                        move || expanded().as_ref() == Some(&name)
+                    };
+                    let contents = section.contents.clone();
+                    let selected_section = section.clone();
+                    let (start, end, size) = (section.range.start, section.range.end, section.size());
+                    view! {
+                        <div class="border-t border-gray-200 dark:border-gray-700">
+                            <div
+                                class="flex justify-between p-2 cursor-pointer hover:bg-gray-200 font-mono dark:hover:bg-gray-600"
+                                on:click=move |_| {
+                                    set_expanded(if expanded().as_ref() == Some(&name) { None } else { Some(name.clone()) });
+                                    on_section_selected(selected_section.clone());
+                                }
+                            >
+                                <span class="font-semibold">{section.name.clone()}</span>
+                                <span>
+                                    {move || {
+                                        let format = settings().format;
+                                        format!("{}..{} ({} bytes)", format.offset(start), format.offset(end), size)
+                                    }}
+                                </span>
+                            </div>
+                            <Show when=is_expanded.clone()>
+                                <ul class="list-none p-0 m-0 pl-4 pb-2 font-mono">
+                                    {contents.iter().map(|line| view! { <li class="truncate">{line.clone()}</li> }).collect::<Vec<_>>()}
+                                </ul>
+                            </Show>
+                        </div>
+                    }
+                }
+            />
+        </div>
+    }
+}
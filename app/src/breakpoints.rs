@@ -0,0 +1,246 @@
+use crate::interpreter::Interpreter;
+use polkavm_common::program::Reg;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    const ALL: [(Comparison, &'static str); 6] = [
+        (Comparison::Equal, "=="),
+        (Comparison::NotEqual, "!="),
+        (Comparison::LessOrEqual, "<="),
+        (Comparison::GreaterOrEqual, ">="),
+        (Comparison::Less, "<"),
+        (Comparison::Greater, ">"),
+    ];
+
+    fn symbol(self) -> &'static str {
+        Self::ALL
+            .iter()
+            .find(|(comparison, _)| *comparison == self)
+            .map_or("?", |(_, symbol)| symbol)
+    }
+
+    fn holds(self, lhs: u32, rhs: u32) -> bool {
+        match self {
+            Comparison::Equal => lhs == rhs,
+            Comparison::NotEqual => lhs != rhs,
+            Comparison::Less => lhs < rhs,
+            Comparison::LessOrEqual => lhs <= rhs,
+            Comparison::Greater => lhs > rhs,
+            Comparison::GreaterOrEqual => lhs >= rhs,
+        }
+    }
+}
+
+/// Register condition such as `a0 == 0x10`; values compare unsigned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Condition {
+    /// Index into `Reg::ALL`
+    pub reg: usize,
+    pub comparison: Comparison,
+    pub value: u32,
+}
+
+impl Condition {
+    pub fn holds(&self, interpreter: &Interpreter) -> bool {
+        Reg::ALL
+            .get(self.reg)
+            .map_or(false, |&reg| self.comparison.holds(interpreter.reg(reg), self.value))
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reg = Reg::ALL.get(self.reg).map_or_else(|| "?".to_string(), |reg| reg.to_string());
+        write!(f, "{} {} 0x{:x}", reg, self.comparison.symbol(), self.value)
+    }
+}
+
+pub fn parse_number(text: &str) -> Option<u32> {
+    let text = text.trim();
+    match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => text
+            .parse::<u32>()
+            .ok()
+            .or_else(|| text.parse::<i32>().ok().map(|value| value as u32)),
+    }
+}
+
+impl FromStr for Condition {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let (position, comparison, symbol) = Comparison::ALL
+            .iter()
+            .find_map(|&(comparison, symbol)| text.find(symbol).map(|position| (position, comparison, symbol)))
+            .ok_or_else(|| format!("Expected a comparison in '{}'", text))?;
+
+        let reg_name = text[..position].trim();
+        let reg = Reg::ALL
+            .iter()
+            .position(|reg| reg.to_string() == reg_name)
+            .ok_or_else(|| format!("Unknown register '{}'", reg_name))?;
+        let value_text = &text[position + symbol.len()..];
+        let value = parse_number(value_text).ok_or_else(|| format!("Invalid value '{}'", value_text.trim()))?;
+
+        Ok(Condition { reg, comparison, value })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Breakpoint {
+    /// Instruction index in the code section
+    pub instruction: usize,
+    pub enabled: bool,
+    pub condition: Option<Condition>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Watchpoint {
+    pub address: u32,
+    pub length: u32,
+    pub on_read: bool,
+    pub on_write: bool,
+    pub enabled: bool,
+}
+
+impl fmt::Display for Watchpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let access = match (self.on_read, self.on_write) {
+            (true, true) => "rw",
+            (true, false) => "r",
+            (false, true) => "w",
+            (false, false) => "-",
+        };
+        write!(
+            f,
+            "0x{:08x}..0x{:08x} ({})",
+            self.address,
+            self.address.saturating_add(self.length),
+            access
+        )
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Breakpoints {
+    pub breakpoints: Vec<Breakpoint>,
+    pub watchpoints: Vec<Watchpoint>,
+}
+
+impl Breakpoints {
+    pub fn storage_key(content_hash: &str) -> String {
+        format!("polkarun.breakpoints.{}", content_hash)
+    }
+
+    pub fn has_breakpoint(&self, instruction: usize) -> bool {
+        self.breakpoints.iter().any(|breakpoint| breakpoint.instruction == instruction)
+    }
+
+    pub fn toggle(&mut self, instruction: usize) {
+        if self.has_breakpoint(instruction) {
+            self.breakpoints.retain(|breakpoint| breakpoint.instruction != instruction);
+        } else {
+            self.breakpoints.push(Breakpoint {
+                instruction,
+                enabled: true,
+                condition: None,
+            });
+            self.breakpoints.sort_by_key(|breakpoint| breakpoint.instruction);
+        }
+    }
+
+    /// Checked between steps: why execution should pause before the next
+    /// instruction, if it should.
+    pub fn hit(&self, interpreter: &Interpreter) -> Option<String> {
+        for watchpoint in self.watchpoints.iter().filter(|watchpoint| watchpoint.enabled) {
+            let range = watchpoint.address..watchpoint.address.saturating_add(watchpoint.length);
+            let access = interpreter.last_accesses().iter().find(|access| {
+                access.overlaps(&range) && ((access.write && watchpoint.on_write) || (!access.write && watchpoint.on_read))
+            });
+            if let Some(access) = access {
+                let kind = if access.write { "write to" } else { "read from" };
+                return Some(format!("watchpoint {}: {} 0x{:08x}", watchpoint, kind, access.address));
+            }
+        }
+
+        let pc = interpreter.pc();
+        self.breakpoints
+            .iter()
+            .filter(|breakpoint| breakpoint.enabled && breakpoint.instruction == pc)
+            .find(|breakpoint| breakpoint.condition.map_or(true, |condition| condition.holds(interpreter)))
+            .map(|breakpoint| match breakpoint.condition {
+                Some(condition) => format!("breakpoint at #{} ({})", pc, condition),
+                None => format!("breakpoint at #{}", pc),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reg_index(reg: Reg) -> usize {
+        Reg::ALL.iter().position(|&other| other == reg).unwrap()
+    }
+
+    #[test]
+    fn parses_hex_decimal_and_negative_numbers() {
+        assert_eq!(parse_number("0x10"), Some(0x10));
+        assert_eq!(parse_number("0X1f"), Some(0x1f));
+        assert_eq!(parse_number(" 42 "), Some(42));
+        assert_eq!(parse_number("4294967295"), Some(u32::MAX));
+        assert_eq!(parse_number("-1"), Some(u32::MAX));
+        assert_eq!(parse_number("-2147483648"), Some(0x8000_0000));
+    }
+
+    #[test]
+    fn rejects_malformed_numbers() {
+        for text in ["", "0x", "0xzz", "abc", "1.5", "4294967296", "-2147483649", "0x100000000"] {
+            assert_eq!(parse_number(text), None, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn parses_conditions() {
+        let condition = |reg, comparison, value| Condition {
+            reg: reg_index(reg),
+            comparison,
+            value,
+        };
+        assert_eq!("a0 == 0x10".parse(), Ok(condition(Reg::A0, Comparison::Equal, 0x10)));
+        assert_eq!(" sp>=4096 ".parse(), Ok(condition(Reg::SP, Comparison::GreaterOrEqual, 4096)));
+        assert_eq!("t0 <= -1".parse(), Ok(condition(Reg::T0, Comparison::LessOrEqual, u32::MAX)));
+        assert_eq!("a5 != 0".parse(), Ok(condition(Reg::A5, Comparison::NotEqual, 0)));
+        assert_eq!("ra < 3".parse(), Ok(condition(Reg::RA, Comparison::Less, 3)));
+        assert_eq!("s1 > 3".parse(), Ok(condition(Reg::S1, Comparison::Greater, 3)));
+    }
+
+    #[test]
+    fn conditions_display_in_a_form_they_parse_from() {
+        let condition = "a1 <= -2".parse::<Condition>().unwrap();
+        assert_eq!(condition.to_string(), "a1 <= 0xfffffffe");
+        assert_eq!(condition.to_string().parse(), Ok(condition));
+    }
+
+    #[test]
+    fn rejects_malformed_conditions() {
+        assert_eq!("a0 = 1".parse::<Condition>(), Err("Expected a comparison in 'a0 = 1'".to_string()));
+        assert_eq!("x9 == 1".parse::<Condition>(), Err("Unknown register 'x9'".to_string()));
+        assert_eq!("== 1".parse::<Condition>(), Err("Unknown register ''".to_string()));
+        assert_eq!("a0 == ".parse::<Condition>(), Err("Invalid value ''".to_string()));
+        assert_eq!("a0 == 0xzz".parse::<Condition>(), Err("Invalid value '0xzz'".to_string()));
+    }
+}
@@ -0,0 +1,262 @@
+use crate::analysis::{EdgeKind, ProgramAnalysis};
+use leptos::*;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::rc::Rc;
+
+const NODE_WIDTH: f64 = 220.0;
+const LINE_HEIGHT: f64 = 14.0;
+const NODE_PADDING: f64 = 8.0;
+const HORIZONTAL_GAP: f64 = 40.0;
+const VERTICAL_GAP: f64 = 48.0;
+// Longer blocks are cut off in the graph; the listing has the full text
+const MAX_NODE_LINES: usize = 8;
+
+#[derive(Clone, Debug, PartialEq)]
+struct GraphNode {
+    jump_target: u32,
+    x: f64,
+    y: f64,
+    height: f64,
+    lines: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct GraphEdge {
+    path: String,
+    kind: EdgeKind,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+struct GraphLayout {
+    nodes: Vec<GraphNode>,
+    edges: Vec<GraphEdge>,
+    width: f64,
+    height: f64,
+}
+
+fn edge_color(kind: EdgeKind) -> &'static str {
+    match kind {
+        EdgeKind::Taken => "#16a34a",
+        EdgeKind::NotTaken => "#dc2626",
+        EdgeKind::Jump => "#2563eb",
+        EdgeKind::Fallthrough | EdgeKind::CallReturn => "#6b7280",
+    }
+}
+
+fn edge_marker(kind: EdgeKind) -> &'static str {
+    match kind {
+        EdgeKind::Taken => "url(#arrow-taken)",
+        EdgeKind::NotTaken => "url(#arrow-not-taken)",
+        EdgeKind::Jump => "url(#arrow-jump)",
+        EdgeKind::Fallthrough | EdgeKind::CallReturn => "url(#arrow-fallthrough)",
+    }
+}
+
+// Layered layout: blocks are ranked by their breadth-first distance from the
+// function entry and laid out left to right within a rank.
+fn layout_function(analysis: &ProgramAnalysis, function_index: usize) -> GraphLayout {
+    let Some(function) = analysis.functions.get(function_index) else {
+        return GraphLayout::default();
+    };
+
+    let mut rank = HashMap::new();
+    let mut queue = VecDeque::from([(function.entry, 0usize)]);
+    while let Some((jump_target, depth)) = queue.pop_front() {
+        if !function.blocks.contains(&jump_target) || rank.contains_key(&jump_target) {
+            continue;
+        }
+        rank.insert(jump_target, depth);
+        if let Some(block) = analysis.block(jump_target) {
+            queue.extend(block.successors.iter().map(|edge| (edge.target, depth + 1)));
+        }
+    }
+
+    let mut ranks = BTreeMap::<usize, Vec<u32>>::new();
+    for (&jump_target, &depth) in &rank {
+        ranks.entry(depth).or_default().push(jump_target);
+    }
+
+    let mut nodes = Vec::new();
+    let mut positions = HashMap::new();
+    let mut y = NODE_PADDING;
+    let mut width: f64 = 0.0;
+    for targets in ranks.values_mut() {
+        targets.sort_unstable();
+        let mut rank_height: f64 = 0.0;
+        for (column, &jump_target) in targets.iter().enumerate() {
+            let Some(block) = analysis.block(jump_target) else {
+                continue;
+            };
+
+            let mut lines = vec![format!("@{}:", jump_target)];
+            lines.extend(
+                analysis.instructions[block.instructions.clone()]
+                    .iter()
+                    .take(MAX_NODE_LINES)
+                    .map(|analyzed| analyzed.instruction.to_string()),
+            );
+            if block.instructions.len() > MAX_NODE_LINES {
+                lines.push(format!("... {} more", block.instructions.len() - MAX_NODE_LINES));
+            }
+
+            let x = NODE_PADDING + column as f64 * (NODE_WIDTH + HORIZONTAL_GAP);
+            let height = lines.len() as f64 * LINE_HEIGHT + NODE_PADDING * 2.0;
+            rank_height = rank_height.max(height);
+            width = width.max(x + NODE_WIDTH + NODE_PADDING);
+            positions.insert(jump_target, (x, y, height));
+            nodes.push(GraphNode { jump_target, x, y, height, lines });
+        }
+        y += rank_height + VERTICAL_GAP;
+    }
+
+    let mut edges = Vec::new();
+    for node in &nodes {
+        let Some(block) = analysis.block(node.jump_target) else {
+            continue;
+        };
+        for edge in &block.successors {
+            let Some(&(target_x, target_y, target_height)) = positions.get(&edge.target) else {
+                continue;
+            };
+            let (source_x, source_y) = (node.x + NODE_WIDTH / 2.0, node.y + node.height);
+            let path = if target_y > node.y {
+                let (target_x, target_y) = (target_x + NODE_WIDTH / 2.0, target_y);
+                let bend = VERTICAL_GAP / 2.0;
+                format!(
+                    "M {} {} C {} {}, {} {}, {} {}",
+                    source_x, source_y, source_x, source_y + bend, target_x, target_y - bend, target_x, target_y
+                )
+            } else {
+                // Back edges loop around the right side of both blocks
+                let right = node.x.max(target_x) + NODE_WIDTH + HORIZONTAL_GAP / 2.0;
+                let (target_x, target_y) = (target_x + NODE_WIDTH, target_y + target_height / 2.0);
+                format!(
+                    "M {} {} C {} {}, {} {}, {} {}",
+                    source_x, source_y, right, source_y + VERTICAL_GAP, right, target_y, target_x, target_y
+                )
+            };
+            edges.push(GraphEdge { path, kind: edge.kind });
+        }
+    }
+
+    GraphLayout {
+        nodes,
+        edges,
+        width: width + HORIZONTAL_GAP,
+        height: y,
+    }
+}
+
+#[component]
+pub fn ControlFlowGraph<F: Fn(u32) + 'static>(
+    analysis: ReadSignal<ProgramAnalysis>,
+    function: ReadSignal<Option<usize>>,
+    selected_block: ReadSignal<Option<u32>>,
+    on_block_selected: F,
+) -> impl IntoView {
+    let on_block_selected = Rc::new(on_block_selected);
+    let layout = create_memo(move |_| {
+        function()
+            .map(|function_index| analysis.with(|analysis| layout_function(analysis, function_index)))
+            .unwrap_or_default()
+    });
+
+    let markers = [
+        ("arrow-taken", EdgeKind::Taken),
+        ("arrow-not-taken", EdgeKind::NotTaken),
+        ("arrow-jump", EdgeKind::Jump),
+        ("arrow-fallthrough", EdgeKind::Fallthrough),
+    ];
+
+    view! {
+        <div class="w-full h-full overflow-auto border border-gray-200 rounded bg-gray-50 dark:border-gray-700 dark:bg-gray-800">
+            <svg
+                xmlns="http://www.w3.org/2000/svg"
+                width=move || layout.with(|layout| layout.width)
+                height=move || layout.with(|layout| layout.height)
+                font-family="monospace"
+                font-size="11"
+            >
+                <defs>
+                    {markers
+                        .into_iter()
+                        .map(|(id, kind)| {
+                            view! {
+                                <marker
+                                    id=id
+                                    viewBox="0 0 10 10"
+                                    refX="10"
+                                    refY="5"
+                                    markerWidth="6"
+                                    markerHeight="6"
+                                    orient="auto-start-reverse"
+                                >
+                                    <path d="M 0 0 L 10 5 L 0 10 z" fill=edge_color(kind)></path>
+                                </marker>
+                            }
+                        })
+                        .collect::<Vec<_>>()}
+                </defs>
+                {move || {
+                    layout
+                        .with(|layout| layout.edges.clone())
+                        .into_iter()
+                        .map(|edge| {
+                            view! {
+                                <path
+                                    d=edge.path
+                                    fill="none"
+                                    stroke=edge_color(edge.kind)
+                                    stroke-width="1.5"
+                                    marker-end=edge_marker(edge.kind)
+                                ></path>
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                }}
+                {move || {
+                    let on_block_selected = on_block_selected.clone();
+                    layout
+                        .with(|layout| layout.nodes.clone())
+                        .into_iter()
+                        .map(|node| {
+                            let on_block_selected = on_block_selected.clone();
+                            let jump_target = node.jump_target;
+                            let is_selected = move || selected_block() == Some(jump_target);
+                            view! {
+                                <g class="cursor-pointer" on:click=move |_| on_block_selected(jump_target)>
+                                    <rect
+                                        x=node.x
+                                        y=node.y
+                                        width=NODE_WIDTH
+                                        height=node.height
+                                        rx="4"
+                                        fill="white"
+                                        stroke=move || if is_selected() { "#552BBF" } else { "#9ca3af" }
+                                        stroke-width=move || if is_selected() { "3" } else { "1" }
+                                    ></rect>
+                                    {node
+                                        .lines
+                                        .iter()
+                                        .enumerate()
+                                        .map(|(index, line)| {
+                                            view! {
+                                                <text
+                                                    x={node.x + NODE_PADDING}
+                                                    y={node.y + NODE_PADDING + (index as f64 + 1.0) * LINE_HEIGHT - 3.0}
+                                                    font-weight={if index == 0 { "bold" } else { "normal" }}
+                                                >
+                                                    {line.clone()}
+                                                </text>
+                                            }
+                                        })
+                                        .collect::<Vec<_>>()}
+                                </g>
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                }}
+            </svg>
+        </div>
+    }
+}
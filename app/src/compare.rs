@@ -0,0 +1,206 @@
+use crate::blob_diff::{compare_blobs, BlobDiff, DiffInstruction, FunctionChange, LineChange};
+use crate::file_upload::FileUploadComponent;
+use leptos::*;
+
+fn change_label(change: FunctionChange) -> &'static str {
+    match change {
+        FunctionChange::Unchanged => "unchanged",
+        FunctionChange::Modified => "modified",
+        FunctionChange::Added => "added",
+        FunctionChange::Removed => "removed",
+    }
+}
+
+fn line_class(change: LineChange) -> &'static str {
+    match change {
+        LineChange::Same => "",
+        LineChange::Inserted => "bg-green-100",
+        LineChange::Removed => "bg-red-100",
+        LineChange::Changed => "bg-yellow-100",
+    }
+}
+
+fn diff_cell(instruction: Option<DiffInstruction>) -> impl IntoView {
+    match instruction {
+        Some(instruction) => view! {
+            <td class="p-1 w-16 text-gray-500">{format!("{:06X}", instruction.offset)}</td>
+            <td class="p-1">{instruction.assembly}</td>
+        }
+        .into_view(),
+        None => view! {
+            <td class="p-1 w-16"></td>
+            <td class="p-1"></td>
+        }
+        .into_view(),
+    }
+}
+
+#[component]
+fn BlobSlot<F: Fn(Vec<u8>, String) + 'static>(label: &'static str, filename: Signal<Option<String>>, on_loaded: F) -> impl IntoView {
+    view! {
+        <div class="w-1/2 p-4 border-dashed border-4">
+            <div class="font-bold">{label}</div>
+            <Show
+                when=move || filename().is_some()
+                fallback=|| view! { <div class="text-sm text-gray-500">"No file loaded"</div> }
+            >
+                <div class="flex items-center text-sm"><div class="i-mdi-file" />{move || filename().unwrap_or_default()}</div>
+            </Show>
+            <FileUploadComponent on_file_uploaded=move |data_option: Option<Vec<u8>>, name: String| {
+                if let Some(data) = data_option {
+                    on_loaded(data, name);
+                }
+            }/>
+        </div>
+    }
+}
+
+/// Side-by-side diff of two blobs, aligned per function.
+#[component]
+pub fn Compare() -> impl IntoView {
+    let (left, set_left) = create_signal(None::<(String, Vec<u8>)>);
+    let (right, set_right) = create_signal(None::<(String, Vec<u8>)>);
+    let (selected_function, set_selected_function) = create_signal(None::<String>);
+    let (hide_unchanged, set_hide_unchanged) = create_signal(true);
+
+    let diff = create_memo(move |_| match (left(), right()) {
+        (Some((_, left)), Some((_, right))) => Some(compare_blobs(&left, &right).map_err(str::to_string)),
+        _ => None,
+    });
+    let diff_result = move |f: &dyn Fn(&BlobDiff) -> View| -> View {
+        diff.with(|diff| match diff {
+            Some(Ok(diff)) => f(diff),
+            Some(Err(message)) => view! { <div class="p-2 text-red-600">{message.clone()}</div> }.into_view(),
+            None => view! { <div class="p-2 text-gray-500">"Load two blobs to compare them"</div> }.into_view(),
+        })
+    };
+
+    let left_filename = Signal::derive(move || left.with(|left| left.as_ref().map(|(name, _)| name.clone())));
+    let right_filename = Signal::derive(move || right.with(|right| right.as_ref().map(|(name, _)| name.clone())));
+
+    view! {
+        <div class="flex flex-col p-4 gap-4">
+            <div class="flex flex-row gap-4">
+                <BlobSlot label="Before" filename=left_filename on_loaded=move |data, name| set_left(Some((name, data)))/>
+                <BlobSlot label="After" filename=right_filename on_loaded=move |data, name| set_right(Some((name, data)))/>
+            </div>
+            <div class="flex flex-row gap-4">
+                <div class="w-1/4 h-70vh overflow-auto border border-gray-200 rounded bg-gray-50 text-xs">
+                    <div class="p-2 font-bold bg-gray-200">"Section sizes"</div>
+                    {move || {
+                        diff_result(&|diff| {
+                            view! {
+                                <table class="w-full font-mono">
+                                    <thead>
+                                        <tr class="text-left bg-gray-100">
+                                            <th class="p-1">"Section"</th>
+                                            <th class="p-1 text-right">"Before"</th>
+                                            <th class="p-1 text-right">"After"</th>
+                                            <th class="p-1 text-right">"Δ"</th>
+                                        </tr>
+                                    </thead>
+                                    <tbody>
+                                        {diff
+                                            .sections
+                                            .iter()
+                                            .map(|section| {
+                                                let delta = section.delta();
+                                                view! {
+                                                    <tr class="border-t border-gray-200">
+                                                        <td class="p-1">{section.name.clone()}</td>
+                                                        <td class="p-1 text-right">{section.left.to_string()}</td>
+                                                        <td class="p-1 text-right">{section.right.to_string()}</td>
+                                                        <td
+                                                            class="p-1 text-right"
+                                                            class:text-red-600=delta > 0
+                                                            class:text-green-600=delta < 0
+                                                        >
+                                                            {format!("{:+}", delta)}
+                                                        </td>
+                                                    </tr>
+                                                }
+                                            })
+                                            .collect::<Vec<_>>()}
+                                    </tbody>
+                                </table>
+                            }
+                            .into_view()
+                        })
+                    }}
+                    <div class="p-2 font-bold bg-gray-200 flex items-center">
+                        "Functions"
+                        <label class="ml-auto flex items-center gap-1 font-normal">
+                            <input
+                                type="checkbox"
+                                prop:checked=hide_unchanged
+                                on:change=move |event| set_hide_unchanged(event_target_checked(&event))
+                            />
+                            "Hide unchanged"
+                        </label>
+                    </div>
+                    {move || {
+                        let hide = hide_unchanged();
+                        let selected = selected_function();
+                        diff_result(&|diff| {
+                            diff.functions
+                                .iter()
+                                .filter(|function| !hide || function.change != FunctionChange::Unchanged)
+                                .map(|function| {
+                                    let name = function.name.clone();
+                                    let is_selected = selected.as_ref() == Some(&function.name);
+                                    let counts = format!(
+                                        "+{} -{} ~{}",
+                                        function.count(LineChange::Inserted),
+                                        function.count(LineChange::Removed),
+                                        function.count(LineChange::Changed)
+                                    );
+                                    view! {
+                                        <div
+                                            class="flex justify-between p-1 font-mono cursor-pointer hover:bg-gray-200"
+                                            class:bg-yellow-100=is_selected
+                                            on:click=move |_| set_selected_function(Some(name.clone()))
+                                        >
+                                            <span class="truncate">{function.name.clone()}</span>
+                                            <span class="text-gray-500">{format!("{} {}", change_label(function.change), counts)}</span>
+                                        </div>
+                                    }
+                                })
+                                .collect::<Vec<_>>()
+                                .into_view()
+                        })
+                    }}
+                </div>
+                <div class="w-3/4 h-70vh overflow-auto border border-gray-200 rounded">
+                    {move || {
+                        let selected = selected_function();
+                        diff_result(&|diff| {
+                            let Some(function) = diff.functions.iter().find(|function| Some(&function.name) == selected.as_ref()) else {
+                                return view! { <div class="p-2 text-gray-500">"Select a function"</div> }.into_view();
+                            };
+                            view! {
+                                <div class="p-2 font-bold bg-gray-200">{format!("fn {}", function.name)}</div>
+                                <table class="w-full text-sm font-mono">
+                                    <tbody>
+                                        {function
+                                            .lines
+                                            .iter()
+                                            .map(|line| {
+                                                view! {
+                                                    <tr class=format!("border-t border-gray-100 {}", line_class(line.change))>
+                                                        {diff_cell(line.left.clone())}
+                                                        {diff_cell(line.right.clone())}
+                                                    </tr>
+                                                }
+                                            })
+                                            .collect::<Vec<_>>()}
+                                    </tbody>
+                                </table>
+                            }
+                            .into_view()
+                        })
+                    }}
+                </div>
+            </div>
+        </div>
+    }
+}
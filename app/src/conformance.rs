@@ -0,0 +1,168 @@
+use crate::file_upload::FileUploadComponent;
+use crate::test_vectors::{parse_vectors, run_vector, VectorOutcome, VectorResult, CHECK_NAMES};
+use leptos::*;
+
+#[component]
+fn DiffTable(result: VectorResult) -> impl IntoView {
+    match result.outcome {
+        VectorOutcome::Error(message) => view! { <div class="p-2 text-red-600">{message}</div> }.into_view(),
+        VectorOutcome::Ran(checks) => {
+            let diffs = checks.into_iter().flat_map(|check| check.diffs).collect::<Vec<_>>();
+            if diffs.is_empty() {
+                return view! { <div class="p-2 text-gray-500">"Final state matches"</div> }.into_view();
+            }
+            view! {
+                <table class="w-full text-xs font-mono">
+                    <thead>
+                        <tr class="text-left bg-gray-100">
+                            <th class="p-1">"Field"</th>
+                            <th class="p-1">"Expected"</th>
+                            <th class="p-1">"Actual"</th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        {diffs
+                            .into_iter()
+                            .map(|diff| {
+                                view! {
+                                    <tr class="border-t border-gray-200">
+                                        <td class="p-1">{diff.field}</td>
+                                        <td class="p-1 text-green-700 break-all">{diff.expected}</td>
+                                        <td class="p-1 text-red-700 break-all">{diff.actual}</td>
+                                    </tr>
+                                }
+                            })
+                            .collect::<Vec<_>>()}
+                    </tbody>
+                </table>
+            }
+            .into_view()
+        }
+    }
+}
+
+/// Runs PVM conformance test vectors from local files against the emulator.
+#[component]
+pub fn Conformance() -> impl IntoView {
+    let results = create_rw_signal(Vec::<VectorResult>::new());
+    let (errors, set_errors) = create_signal(Vec::<String>::new());
+    let (selected, set_selected) = create_signal(None::<usize>);
+
+    let on_file_uploaded = move |data_option: Option<Vec<u8>>, filename: String| {
+        let Some(data) = data_option else {
+            return;
+        };
+        let parsed = String::from_utf8(data)
+            .map_err(|_| "not valid UTF-8".to_string())
+            .and_then(|contents| parse_vectors(&contents));
+        match parsed {
+            Ok(vectors) => {
+                let ran = vectors.iter().map(|vector| run_vector(&filename, vector)).collect::<Vec<_>>();
+                results.update(|results| {
+                    // Reloading a file replaces its earlier results
+                    results.retain(|result| result.file != filename);
+                    results.extend(ran);
+                });
+            }
+            Err(message) => set_errors.update(|errors| errors.push(format!("{}: {}", filename, message))),
+        }
+    };
+
+    let summary = move || {
+        results.with(|results| {
+            let passed = results.iter().filter(|result| result.passed()).count();
+            let errored = results
+                .iter()
+                .filter(|result| matches!(result.outcome, VectorOutcome::Error(_)))
+                .count();
+            format!("{} passed, {} failed, {} could not run", passed, results.len() - passed - errored, errored)
+        })
+    };
+
+    view! {
+        <div class="flex flex-col p-4 gap-4">
+            <div class="p-4 border-dashed border-4">
+                <FileUploadComponent accept=".json" multiple=true on_file_uploaded=on_file_uploaded/>
+            </div>
+            {move || {
+                errors()
+                    .into_iter()
+                    .map(|message| view! { <div class="text-sm text-red-600">{message}</div> })
+                    .collect::<Vec<_>>()
+            }}
+            <div class="flex items-center gap-4 text-sm">
+                <span class="font-mono">{summary}</span>
+                <button
+                    class="px-3 py-1 text-sm font-semibold text-gray-700 bg-white hover:bg-gray-100 rounded-xs border border-gray-300"
+                    on:click=move |_| {
+                        results.set(Vec::new());
+                        set_errors(Vec::new());
+                        set_selected(None);
+                    }
+                >
+                    "Clear"
+                </button>
+            </div>
+            <div class="flex flex-row gap-4">
+                <div class="w-1/2 h-70vh overflow-auto border border-gray-200 rounded">
+                    <table class="w-full text-xs font-mono">
+                        <thead>
+                            <tr class="text-left bg-gray-200">
+                                <th class="p-1">"Vector"</th>
+                                {CHECK_NAMES.into_iter().map(|name| view! { <th class="p-1 text-center">{name}</th> }).collect::<Vec<_>>()}
+                            </tr>
+                        </thead>
+                        <tbody>
+                            {move || {
+                                results()
+                                    .into_iter()
+                                    .enumerate()
+                                    .map(|(index, result)| {
+                                        let cells = match &result.outcome {
+                                            VectorOutcome::Ran(checks) => checks
+                                                .iter()
+                                                .map(|check| {
+                                                    let (mark, color) = if check.passed() { ("✓", "text-green-600") } else { ("✗", "text-red-600") };
+                                                    view! { <td class=format!("p-1 text-center {}", color)>{mark}</td> }
+                                                })
+                                                .collect::<Vec<_>>(),
+                                            VectorOutcome::Error(_) => vec![
+                                                view! {
+                                                    <td class="p-1 text-center text-gray-500" colspan=CHECK_NAMES.len().to_string()>"error"</td>
+                                                },
+                                            ],
+                                        };
+                                        view! {
+                                            <tr
+                                                class="border-t border-gray-200 cursor-pointer hover:bg-gray-100"
+                                                class:bg-yellow-100=move || selected() == Some(index)
+                                                title=result.file.clone()
+                                                on:click=move |_| set_selected(Some(index))
+                                            >
+                                                <td class="p-1">{result.name.clone()}</td>
+                                                {cells}
+                                            </tr>
+                                        }
+                                    })
+                                    .collect::<Vec<_>>()
+                            }}
+                        </tbody>
+                    </table>
+                </div>
+                <div class="w-1/2 h-70vh overflow-auto border border-gray-200 rounded bg-gray-50">
+                    {move || {
+                        let result = selected().and_then(|index| results.with(|results| results.get(index).cloned()));
+                        match result {
+                            Some(result) => view! {
+                                <div class="p-2 font-bold bg-gray-200">{format!("{} ({})", result.name, result.file)}</div>
+                                <DiffTable result=result/>
+                            }
+                            .into_view(),
+                            None => view! { <div class="p-2 text-gray-500">"Select a vector to see its diff"</div> }.into_view(),
+                        }
+                    }}
+                </div>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,96 @@
+use crate::analysis::ProgramAnalysis;
+
+/// Execution count of a single instruction or block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HotSpot {
+    /// Instruction index of the instruction or of the block's first instruction
+    pub instruction: usize,
+    /// Byte offset in the blob
+    pub offset: usize,
+    pub label: String,
+    pub count: u64,
+    /// Share of all executed instructions, in percent
+    pub share: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HotSpotKind {
+    Instructions,
+    Blocks,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HotSpotOrder {
+    Count,
+    Offset,
+}
+
+/// Heat bucket from 0 (never executed) to `HEAT_LEVELS`, on a log scale so
+/// that a few hot loops don't wash out everything else.
+pub const HEAT_LEVELS: u32 = 5;
+
+pub fn heat_level(count: u64, max_count: u64) -> u32 {
+    if count == 0 || max_count == 0 {
+        return 0;
+    }
+    let ratio = ((count as f64).ln_1p() / (max_count as f64).ln_1p()).clamp(0.0, 1.0);
+    1 + (ratio * f64::from(HEAT_LEVELS - 1)).round() as u32
+}
+
+/// Instructions or blocks with their execution counts, never-executed ones
+/// left out.
+pub fn hot_spots(analysis: &ProgramAnalysis, counts: &[u64], kind: HotSpotKind, order: HotSpotOrder) -> Vec<HotSpot> {
+    let total = counts.iter().sum::<u64>().max(1) as f64;
+    let offset_of = |index: usize| {
+        analysis
+            .instructions
+            .get(index)
+            .map_or(0, |analyzed| analysis.code_offset + analyzed.offset)
+    };
+
+    let mut spots = match kind {
+        HotSpotKind::Instructions => counts
+            .iter()
+            .enumerate()
+            .filter(|(_, &count)| count > 0)
+            .map(|(index, &count)| HotSpot {
+                instruction: index,
+                offset: offset_of(index),
+                label: analysis
+                    .instructions
+                    .get(index)
+                    .map_or_else(String::new, |analyzed| analyzed.instruction.to_string()),
+                count,
+                share: count as f64 * 100.0 / total,
+            })
+            .collect::<Vec<_>>(),
+        HotSpotKind::Blocks => analysis
+            .blocks
+            .iter()
+            .filter_map(|block| {
+                let instructions = counts.get(block.instructions.clone())?;
+                let entries = *instructions.first()?;
+                if entries == 0 {
+                    return None;
+                }
+                let executed = instructions.iter().sum::<u64>();
+                let function = analysis
+                    .function_of_block(block.jump_target)
+                    .map_or_else(String::new, |function| format!(" in {}", function.name));
+                Some(HotSpot {
+                    instruction: block.instructions.start,
+                    offset: offset_of(block.instructions.start),
+                    label: format!("@{}{}", block.jump_target, function),
+                    count: entries,
+                    share: executed as f64 * 100.0 / total,
+                })
+            })
+            .collect(),
+    };
+
+    match order {
+        HotSpotOrder::Count => spots.sort_by(|a, b| b.count.cmp(&a.count).then(a.offset.cmp(&b.offset))),
+        HotSpotOrder::Offset => spots.sort_by_key(|spot| spot.offset),
+    }
+    spots
+}
@@ -0,0 +1,88 @@
+use crate::coverage::{hot_spots, HotSpotKind, HotSpotOrder};
+use crate::interpreter::Interpreter;
+use leptos::*;
+
+const HOT_SPOT_ROWS: usize = 50;
+
+/// Row background for a `heat_level`.
+pub fn heat_class(level: u32) -> &'static str {
+    match level {
+        0 => "text-gray-400 bg-gray-50",
+        1 => "bg-orange-50",
+        2 => "bg-orange-100",
+        3 => "bg-orange-200",
+        4 => "bg-orange-300",
+        _ => "bg-red-300",
+    }
+}
+
+#[component]
+pub fn HotSpotPanel<F: Fn(usize) + Copy + 'static>(interpreter: RwSignal<Option<Interpreter>>, on_selected: F) -> impl IntoView {
+    let (kind, set_kind) = create_signal(HotSpotKind::Blocks);
+    let (order, set_order) = create_signal(HotSpotOrder::Count);
+
+    let spots = create_memo(move |_| {
+        let (kind, order) = (kind(), order());
+        interpreter.with(|vm| {
+            vm.as_ref()
+                .map(|vm| hot_spots(&vm.program().analysis, vm.execution_counts(), kind, order))
+                .unwrap_or_default()
+        })
+    });
+
+    let tab_class = move |tab: HotSpotKind| {
+        if kind() == tab {
+            "px-2 font-bold border-b-2 border-gray-700 cursor-pointer"
+        } else {
+            "px-2 text-gray-600 cursor-pointer"
+        }
+    };
+    let header = move |label: &'static str, column: HotSpotOrder| {
+        view! {
+            <th class="p-1 cursor-pointer hover:bg-gray-200" on:click=move |_| set_order(column)>
+                {move || if order() == column { format!("{} ▾", label) } else { label.to_string() }}
+            </th>
+        }
+    };
+
+    view! {
+        <div class="p-2 font-bold bg-gray-200 flex items-center">
+            "Hot spots"
+            <span class="ml-auto text-xs font-normal flex">
+                <span class=move || tab_class(HotSpotKind::Blocks) on:click=move |_| set_kind(HotSpotKind::Blocks)>"Blocks"</span>
+                <span class=move || tab_class(HotSpotKind::Instructions) on:click=move |_| set_kind(HotSpotKind::Instructions)>
+                    "Instructions"
+                </span>
+            </span>
+        </div>
+        <table class="w-full text-xs font-mono">
+            <thead>
+                <tr class="text-left bg-gray-100">
+                    {header("Offset", HotSpotOrder::Offset)}
+                    <th class="p-1">{move || if kind() == HotSpotKind::Blocks { "Block" } else { "Instruction" }}</th>
+                    {header("Count", HotSpotOrder::Count)}
+                    <th class="p-1">"%"</th>
+                </tr>
+            </thead>
+            <tbody>
+                {move || {
+                    spots()
+                        .into_iter()
+                        .take(HOT_SPOT_ROWS)
+                        .map(|spot| {
+                            let instruction = spot.instruction;
+                            view! {
+                                <tr class="border-t border-gray-200 cursor-pointer hover:bg-gray-200" on:click=move |_| on_selected(instruction)>
+                                    <td class="p-1">{format!("{:06X}", spot.offset)}</td>
+                                    <td class="p-1 truncate">{spot.label}</td>
+                                    <td class="p-1">{spot.count.to_string()}</td>
+                                    <td class="p-1">{format!("{:.1}", spot.share)}</td>
+                                </tr>
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                }}
+            </tbody>
+        </table>
+    }
+}
@@ -0,0 +1,173 @@
+use crate::storage;
+use js_sys::Uint8Array;
+use rexie::{ObjectStore, Rexie, TransactionMode};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::cell::OnceCell;
+use std::rc::Rc;
+use wasm_bindgen::{JsCast, JsValue};
+
+const DATABASE_NAME: &str = "polkarun";
+const DATABASE_VERSION: u32 = 1;
+
+// Blob contents, keyed by content hash
+const BLOBS: &str = "blobs";
+// `StoredFile` for each blob, kept apart so listing recent files doesn't read
+// every blob
+const FILES: &str = "files";
+// Per-file state such as breakpoints, annotations and view positions, keyed
+// like `storage::save` keys
+const STATE: &str = "state";
+
+thread_local! {
+    // Opened on first use and kept open for the lifetime of the page
+    static DATABASE: OnceCell<Rc<Rexie>> = const { OnceCell::new() };
+}
+
+/// What is remembered about a loaded blob besides its contents.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct StoredFile {
+    pub hash: String,
+    pub filename: String,
+    pub size: usize,
+    /// Milliseconds since the epoch
+    pub last_opened: f64,
+}
+
+async fn open() -> Result<Rexie, String> {
+    Rexie::builder(DATABASE_NAME)
+        .version(DATABASE_VERSION)
+        .add_object_store(ObjectStore::new(BLOBS))
+        .add_object_store(ObjectStore::new(FILES))
+        .add_object_store(ObjectStore::new(STATE))
+        .build()
+        .await
+        .map_err(|error| format!("Failed to open the database: {}", error))
+}
+
+async fn database() -> Result<Rc<Rexie>, String> {
+    if let Some(database) = DATABASE.with(|database| database.get().cloned()) {
+        return Ok(database);
+    }
+    let opened = Rc::new(open().await?);
+    // Whichever caller finished opening it first wins
+    Ok(DATABASE.with(|database| database.get_or_init(|| opened).clone()))
+}
+
+// Values other than blobs are stored as RON strings, like in local storage
+fn encode<T: Serialize>(value: &T) -> Result<JsValue, String> {
+    ron::to_string(value)
+        .map(|serialized| JsValue::from_str(&serialized))
+        .map_err(|error| format!("Failed to serialize: {}", error))
+}
+
+fn decode<T: DeserializeOwned>(value: JsValue) -> Result<Option<T>, String> {
+    let Some(serialized) = value.as_string() else {
+        return Ok(None);
+    };
+    ron::from_str(&serialized)
+        .map(Some)
+        .map_err(|error| format!("Failed to deserialize: {}", error))
+}
+
+async fn put(store_name: &str, key: &str, value: &JsValue) -> Result<(), String> {
+    let database = database().await?;
+    let transaction = database
+        .transaction(&[store_name], TransactionMode::ReadWrite)
+        .map_err(|error| error.to_string())?;
+    let store = transaction.store(store_name).map_err(|error| error.to_string())?;
+    store
+        .put(value, Some(&JsValue::from_str(key)))
+        .await
+        .map_err(|error| error.to_string())?;
+    transaction.done().await.map_err(|error| error.to_string())
+}
+
+async fn get(store_name: &str, key: &str) -> Result<JsValue, String> {
+    let database = database().await?;
+    let transaction = database
+        .transaction(&[store_name], TransactionMode::ReadOnly)
+        .map_err(|error| error.to_string())?;
+    let store = transaction.store(store_name).map_err(|error| error.to_string())?;
+    store.get(&JsValue::from_str(key)).await.map_err(|error| error.to_string())
+}
+
+/// Stores `data` under its content hash and marks it as just opened.
+pub async fn save_file(hash: &str, filename: &str, data: &[u8]) -> Result<(), String> {
+    put(BLOBS, hash, &Uint8Array::from(data).into()).await?;
+    let file = StoredFile {
+        hash: hash.to_string(),
+        filename: filename.to_string(),
+        size: data.len(),
+        last_opened: js_sys::Date::now(),
+    };
+    put(FILES, hash, &encode(&file)?).await
+}
+
+pub async fn load_file(hash: &str) -> Result<Option<(StoredFile, Vec<u8>)>, String> {
+    let Some(file) = decode::<StoredFile>(get(FILES, hash).await?)? else {
+        return Ok(None);
+    };
+    let Ok(data) = get(BLOBS, hash).await?.dyn_into::<Uint8Array>() else {
+        return Ok(None);
+    };
+    Ok(Some((file, data.to_vec())))
+}
+
+/// Stored files, most recently opened first.
+pub async fn recent_files() -> Result<Vec<StoredFile>, String> {
+    let database = database().await?;
+    let transaction = database
+        .transaction(&[FILES], TransactionMode::ReadOnly)
+        .map_err(|error| error.to_string())?;
+    let store = transaction.store(FILES).map_err(|error| error.to_string())?;
+    let entries = store
+        .get_all(None, None, None, None)
+        .await
+        .map_err(|error| error.to_string())?;
+
+    let mut files = Vec::new();
+    for (_, value) in entries {
+        match decode::<StoredFile>(value) {
+            Ok(Some(file)) => files.push(file),
+            Ok(None) => {}
+            Err(error) => log::warn!("Ignoring stored file: {}", error),
+        }
+    }
+    files.sort_by(|a, b| b.last_opened.total_cmp(&a.last_opened));
+    Ok(files)
+}
+
+/// Forgets a stored file. State saved under keys derived from its hash is
+/// left alone so it comes back if the same file is loaded again.
+pub async fn forget_file(hash: &str) -> Result<(), String> {
+    let database = database().await?;
+    let transaction = database
+        .transaction(&[BLOBS, FILES], TransactionMode::ReadWrite)
+        .map_err(|error| error.to_string())?;
+    for store_name in [BLOBS, FILES] {
+        let store = transaction.store(store_name).map_err(|error| error.to_string())?;
+        store
+            .delete(&JsValue::from_str(hash))
+            .await
+            .map_err(|error| error.to_string())?;
+    }
+    transaction.done().await.map_err(|error| error.to_string())
+}
+
+/// Loads per-file state. State saved to local storage before it moved here,
+/// such as breakpoints, is moved over the first time it is loaded.
+pub async fn load_state<T: DeserializeOwned + Serialize>(key: &str) -> Result<Option<T>, String> {
+    if let Some(value) = decode(get(STATE, key).await?)? {
+        return Ok(Some(value));
+    }
+    let Some(value) = storage::load::<T>(key) else {
+        return Ok(None);
+    };
+    save_state(key, &value).await?;
+    storage::remove(key);
+    Ok(Some(value))
+}
+
+pub async fn save_state<T: Serialize>(key: &str, value: &T) -> Result<(), String> {
+    put(STATE, key, &encode(value)?).await
+}
@@ -1,378 +1,705 @@
 use leptos::*;
-use polkavm_common::program::{ProgramBlob, Instruction};
+use polkavm_common::program::ProgramBlob;
+use polkarun_core::listing::{disassemble_into_lines, unified_representation, DisassembledLine};
 use crate::file_upload::FileUploadComponent;
+use crate::blob_structure::{blob_sections, BlobSection, BlobStructure};
+use crate::symbols::{program_symbols, ExportSymbol, ProgramSymbols, SymbolTable};
+use crate::analysis::ProgramAnalysis;
+use crate::cfg::ControlFlowGraph;
+use crate::xrefs::XRefPanel;
+use crate::gas::{block_cost, use_cost_model};
+use crate::database::{load_file, load_state, save_file, save_state};
+use crate::recent_files::RecentFiles;
+use crate::storage;
+use crate::fetch::{fetch_blob, url_filename};
+use crate::breakpoints::parse_number;
+use crate::menu::{MainMenu, MenuActions, MenuBar};
+use crate::appearance::{use_appearance, Appearance, Theme};
+use crate::settings::{use_settings, Settings, SettingsDialog};
+use leptos_router::{use_location, use_navigate, use_query_map, NavigateOptions};
+use std::collections::{BTreeMap, HashSet};
+use std::rc::Rc;
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug)]
-struct DisassembledLine {
-    offset: String,
-    hex: String,
-    assembly: String,
-    operation: String,
+fn line_element_id(line: &DisassembledLine) -> String {
+    format!("asm-{}", line.offset)
 }
 
-// Helper function to create a new DisassembledLine
-impl DisassembledLine {
-    fn new(offset: usize, hex: String, assembly: String, operation: String) -> Self {
-        Self {
-            offset: format!("{:06X}", offset),
-            hex,
-            assembly,
-            operation,
-        }
+fn listing_row(
+    line: &DisassembledLine,
+    settings: Settings,
+    is_selected: impl Fn() -> bool + 'static,
+    annotation: Option<String>,
+    on_click: impl Fn() + 'static,
+    on_double_click: impl Fn() + 'static,
+    on_context_menu: impl Fn() + 'static,
+) -> View {
+    let Settings { format, columns } = settings;
+    // Lines reporting a parse error have no bytes, only a message
+    let hex = if line.bytes.is_empty() { line.hex.clone() } else { format.bytes(&line.bytes) };
+    view! {
+        <div
+            id=line_element_id(line)
+            class="flex divide-x divide-gray-200 dark:divide-gray-700"
+            class:ring-2=is_selected
+            on:click=move |_| on_click()
+            on:dblclick=move |_| on_double_click()
+            on:contextmenu=move |event| {
+                event.prevent_default();
+                on_context_menu();
+            }
+        >
+            {columns.offset.then(|| view! {
+                <div class="flex-1 p-2 bg-white dark:bg-gray-900">
+                    <pre class="whitespace-pre-wrap overflow-x-auto">{format.offset(line.address)}</pre>
+                </div>
+            })}
+            {columns.hex.then(|| view! {
+                <div class="flex-1 p-2 bg-white dark:bg-gray-900">
+                    <pre class="whitespace-pre-wrap overflow-x-auto">{hex}</pre>
+                </div>
+            })}
+            {columns.assembly.then(|| view! {
+                <div class="flex-1 p-2 bg-white dark:bg-gray-900">
+                    <pre class="whitespace-pre-wrap overflow-x-auto">
+                        {format.assembly(&line.assembly)}
+                        {line.comment.clone().map(|comment| view! {
+                            <span class="text-gray-500">{format!("  ; {}", comment)}</span>
+                        })}
+                        {annotation.map(|annotation| view! {
+                            <span class="text-blue-600 dark:text-blue-400">{format!("  ; {}", annotation)}</span>
+                        })}
+                    </pre>
+                </div>
+            })}
+            {columns.operation.then(|| view! {
+                <div class="flex-1 p-2 bg-white dark:bg-gray-900">
+                    <pre class="whitespace-pre-wrap overflow-x-auto">{line.operation.clone()}</pre>
+                </div>
+            })}
+        </div>
     }
+    .into_view()
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
-enum MenuItemType {
-    RegularItem,
-    SubMenu(Vec<MenuItem>),
+pub fn scroll_into_view(element_id: &str) {
+    if let Some(element) = document().get_element_by_id(element_id) {
+        element.scroll_into_view();
+    }
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
-struct MenuItem {
-    label: String,
-    item_type: MenuItemType,
+// Everything derived from one loaded blob, kept per file so switching between
+// files doesn't parse anything again
+#[derive(Clone, Debug)]
+struct LoadedFile {
+    id: usize,
+    hash: String,
+    /// URL it was fetched from, or the content hash for uploaded files
+    source: String,
+    filename: String,
+    data: Rc<Vec<u8>>,
+    lines: Vec<DisassembledLine>,
+    sections: Vec<BlobSection>,
+    symbols: ProgramSymbols,
+    analysis: ProgramAnalysis,
+    /// Why the blob couldn't be (fully) analyzed
+    error: Option<String>,
+    view: FileView,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
-struct MainMenu {
-    items: Vec<MenuItem>,
+// Where the user was in a file, restored when switching back to it or
+// opening the same content again later
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+struct FileView {
+    selected_range: Option<std::ops::Range<usize>>,
+    selected_line: Option<String>,
+    collapsed_blocks: HashSet<u32>,
+    selected_function: Option<usize>,
+    selected_block: Option<u32>,
+    xref_target: Option<u32>,
+    /// Notes the user attached to instructions, keyed by byte offset
+    #[serde(default)]
+    annotations: BTreeMap<usize, String>,
 }
 
-#[component]
-fn MenuButton(item: MenuItem) -> impl IntoView {
+impl FileView {
+    fn storage_key(content_hash: &str) -> String {
+        format!("polkarun.view.{}", content_hash)
+    }
+}
 
-    view! {
-        <div
-            role="menuitem"
-            class="menu-button px-4 py-2 text-md font-semibold text-gray-700 bg-white hover:bg-gray-100 focus:bg-gray-200 rounded-xs border border-gray-300 shadow-sm cursor-pointer focus:outline-none focus:ring-2 focus:ring-blue-500 focus:ring-opacity-50"
-        >
-            {&item.label}
-        </div>
+fn analyze_file(id: usize, hash: String, source: String, filename: String, data: &[u8]) -> LoadedFile {
+    let mut errors = Vec::new();
+    let (lines, analysis) = disassemble_into_lines(data).unwrap_or_else(|error| {
+        errors.push(error);
+        (Vec::new(), ProgramAnalysis::default())
+    });
+
+    let symbols = ProgramBlob::parse(data)
+        .map_err(|_| "Failed to parse blob")
+        .and_then(|blob| program_symbols(&blob))
+        .unwrap_or_else(|error| {
+            errors.push(error);
+            ProgramSymbols::default()
+        });
+
+    let sections = blob_sections(data).unwrap_or_else(|error| {
+        errors.push(error);
+        Vec::new()
+    });
+
+    // A blob that doesn't parse fails every step the same way
+    errors.dedup();
+    for error in &errors {
+        log::error!("{}: {}", filename, error);
+    }
+
+    LoadedFile {
+        id,
+        hash,
+        source,
+        filename,
+        data: Rc::new(data.to_vec()),
+        lines,
+        sections,
+        symbols,
+        analysis,
+        error: (!errors.is_empty()).then(|| errors.join("; ")),
+        view: FileView::default(),
     }
 }
 
-// MainMenu
-#[component]
-fn MainMenu() -> impl IntoView {
-
-    let menu = MainMenu {
-        items: vec![
-            MenuItem {
-                label: "File".to_string(),
-                item_type: MenuItemType::SubMenu(vec![
-                    MenuItem {
-                        label: "Load New".to_string(),
-                        item_type: MenuItemType::RegularItem,
-                    },
-                    MenuItem {
-                        label: "Unload All".to_string(),
-                        item_type: MenuItemType::RegularItem,
-                    },
-                ]),
-            },
-            MenuItem {
-                label: "Settings".to_string(),
-                item_type: MenuItemType::RegularItem,
-            },
-            MenuItem {
-                label: "View".to_string(),
-                item_type: MenuItemType::SubMenu(vec![
-                    MenuItem {
-                        label: "Style".to_string(),
-                        item_type: MenuItemType::SubMenu(vec![
-                            MenuItem {
-                                label: "System Default".to_string(),
-                                item_type: MenuItemType::RegularItem,
-                            },
-                            MenuItem {
-                                label: "Day Mode".to_string(),
-                                item_type: MenuItemType::RegularItem,
-                            },
-                            MenuItem {
-                                label: "Dark Mode".to_string(),
-                                item_type: MenuItemType::RegularItem,
-                            },
-                        ]),
-                    },
-                    MenuItem {
-                        label: "Zoom".to_string(),
-                        item_type: MenuItemType::SubMenu(vec![
-                            MenuItem {
-                                label: "Zoom: {zoom_level}%".to_string(),
-                                item_type: MenuItemType::RegularItem,
-                            },
-                            MenuItem {
-                                label: "Zoom in (+)".to_string(),
-                                item_type: MenuItemType::RegularItem,
-                            },
-                            MenuItem {
-                                label: "Zoom out (-)".to_string(),
-                                item_type: MenuItemType::RegularItem,
-                            },
-                            MenuItem {
-                                label: "Default Size".to_string(),
-                                item_type: MenuItemType::RegularItem,
-                            },
-                        ]),
-                    },
-                ]),
-            },
-            MenuItem {
-                label: "Compare".to_string(),
-                item_type: MenuItemType::RegularItem,
-            },
-            MenuItem {
-                label: "Info".to_string(),
-                item_type: MenuItemType::RegularItem,
-            },
-        ],
-    };
+// Adds a newly opened file and shows it. Effects that follow the active file
+// look it up in `files`, so it has to be there before it is shown.
+fn add_file(files: RwSignal<Vec<LoadedFile>>, file: LoadedFile, show_file: impl Fn(Option<&LoadedFile>)) {
+    let id = file.id;
+    files.update(|files| files.push(file));
+    files.with_untracked(|files| show_file(files.iter().find(|file| file.id == id)));
+}
 
-    view! {
-        <nav
-            role="menubar"
-            class="flex h-10 items-center space-x-1 rounded-md border-0 bg-background p-1"
-            tabindex="0"
-            data-orientation="horizontal"
-            style="outline:none"
-        >
-            <For
-                each=move || menu.items.clone().into_iter()
-                key=|item| item.label.clone()
-                children=move |item| {
-                    view! { <MenuButton item=item.clone()/> }
-                }
-            />
+// The `src` and fragment of the deep link to a file and its selected line
+fn link_target(files: &[LoadedFile], id: usize, selected_line: Option<&str>) -> Option<(String, String)> {
+    let file = files.iter().find(|file| file.id == id)?;
+    let fragment = selected_line.map(|offset| format!("0x{}", offset)).unwrap_or_default();
+    Some((file.source.clone(), fragment))
+}
 
-        </nav>
-    }
+// Handlers for every action of the menu file
+fn menu_actions(
+    set_show_upload: WriteSignal<bool>,
+    set_show_settings: WriteSignal<bool>,
+    unload_all: impl Fn() + 'static,
+    appearance: RwSignal<Appearance>,
+) -> MenuActions {
+    let set_theme = move |theme: Theme| appearance.update(|appearance| appearance.theme = theme);
+    MenuActions::new()
+        .register("file_load_new", move || set_show_upload(true))
+        .register("file_open_url", move || set_show_upload(true))
+        .register("file_unload_all", unload_all)
+        .register("settings_open", move || set_show_settings(true))
+        .register("style_system_default", move || set_theme(Theme::System))
+        .register("style_day_mode", move || set_theme(Theme::Day))
+        .register("style_dark_mode", move || set_theme(Theme::Dark))
+        .register("zoom_in", move || appearance.update(Appearance::zoom_in))
+        .register("zoom_out", move || appearance.update(Appearance::zoom_out))
+        .register("zoom_default", move || appearance.update(Appearance::reset_zoom))
+        .value("zoom_level", move || appearance().zoom.to_string())
 }
 
 // Main component
 #[component]
 pub fn Disassembler() -> impl IntoView {
 
-    let (unified_data, set_unified_data) = create_signal(Vec::new());
-    let (chunk_size, set_chunk_size) = create_signal(0u8);
-    let (filename, set_filename) = create_signal(String::new());
-    let (show_file_options, set_show_file_options)  = create_signal(false);
+    let settings = use_settings();
+    let cost_model = use_cost_model();
+    let format = create_memo(move |_| settings().format);
+    let columns = create_memo(move |_| settings().columns);
+
+    let (blob_data, set_blob_data) = create_signal(Rc::new(Vec::<u8>::new()));
+    let unified_data = create_memo(move |_| blob_data.with(|data| unified_representation(data, &format())));
+    let chunk_size = create_memo(move |_| {
+        if blob_data.with(|data| data.is_empty()) {
+            0
+        } else {
+            format().bytes_per_row.max(1)
+        }
+    });
 
     let (disassembled_data, set_disassembled_data) = create_signal(Vec::<DisassembledLine>::new());
+    let (sections, set_sections) = create_signal(Vec::<BlobSection>::new());
+    let (selected_range, set_selected_range) = create_signal(None::<std::ops::Range<usize>>);
+    let (symbols, set_symbols) = create_signal(ProgramSymbols::default());
+    let (selected_line, set_selected_line) = create_signal(None::<String>);
+    let (analysis, set_analysis) = create_signal(ProgramAnalysis::default());
+    let (collapsed_blocks, set_collapsed_blocks) = create_signal(HashSet::<u32>::new());
+    let (selected_function, set_selected_function) = create_signal(None::<usize>);
+    let (selected_block, set_selected_block) = create_signal(None::<u32>);
+
+    let (xref_target, set_xref_target) = create_signal(None::<u32>);
+    let (annotations, set_annotations) = create_signal(BTreeMap::<usize, String>::new());
+
+    // Double-clicking an instruction edits its annotation; an empty one
+    // removes it
+    let annotate = move |address: usize| {
+        let current = annotations.with_untracked(|annotations| annotations.get(&address).cloned().unwrap_or_default());
+        let Ok(Some(text)) = window().prompt_with_message_and_default("Annotation", &current) else {
+            return;
+        };
+        set_annotations.update(|annotations| match text.trim() {
+            "" => {
+                annotations.remove(&address);
+            }
+            text => {
+                annotations.insert(address, text.to_string());
+            }
+        });
+    };
 
+    let select_instruction = move |index: usize| {
+        let block = analysis.with_untracked(|analysis| analysis.block_of_instruction(index).map(|block| block.jump_target));
+        if let Some(jump_target) = block {
+            set_collapsed_blocks.update(|collapsed| {
+                collapsed.remove(&jump_target);
+            });
+        }
+        if let Some(line) = disassembled_data.with_untracked(|lines| lines.get(index).cloned()) {
+            scroll_into_view(&line_element_id(&line));
+            set_selected_line(Some(line.offset));
+        }
+    };
 
-    fn unified_representation(data: &[u8], chunk_size: usize) -> Vec<String> {
-        let hex_length = chunk_size * 3 - 1; // Expected length of the hex part
-
-        data.chunks(chunk_size)
-            .enumerate()
-            .map(move |(index, chunk)| {
-                let current_offset = index * chunk_size; // Calculate offset here
-
-                // Initialize hex_part and text_part
-                let mut hex_part = String::with_capacity(hex_length);
-                let mut text_part = String::with_capacity(chunk_size);
+    // Brings a block into view in both the listing and the hex dump
+    let select_block = move |jump_target: u32| {
+        let (line, byte_range) = analysis.with_untracked(|analysis| {
+            let line = analysis
+                .block(jump_target)
+                .and_then(|block| disassembled_data.with_untracked(|lines| lines.get(block.instructions.start).cloned()));
+            (line, analysis.block_byte_range(jump_target))
+        });
+
+        set_selected_block(Some(jump_target));
+        set_collapsed_blocks.update(|collapsed| {
+            collapsed.remove(&jump_target);
+        });
+        if let Some(line) = line {
+            scroll_into_view(&line_element_id(&line));
+            set_selected_line(Some(line.offset));
+        }
+        if let Some(byte_range) = byte_range {
+            let row_size = chunk_size.get_untracked();
+            if row_size > 0 {
+                scroll_into_view(&format!("hex-row-{}", byte_range.start / row_size));
+            }
+            set_selected_range(Some(byte_range));
+        }
+    };
 
-                for &byte in chunk {
-                    // Append to hex_part and text_part
-                    hex_part.push_str(&format!("{:02x} ", byte));
-                    text_part.push(if (32..=126).contains(&byte) { byte as char } else { '.' });
+    // Selects the instruction at a byte offset in the blob, or highlights the
+    // byte in the hex dump when it isn't code
+    let go_to_offset = move |offset: usize| {
+        let index = disassembled_data.with_untracked(|lines| {
+            lines
+                .iter()
+                .position(|line| line.byte_range().contains(&offset))
+        });
+        match index {
+            Some(index) => select_instruction(index),
+            None => {
+                let row_size = chunk_size.get_untracked();
+                if row_size > 0 {
+                    scroll_into_view(&format!("hex-row-{}", offset / row_size));
                 }
+                set_selected_range(Some(offset..offset + 1));
+            }
+        }
+    };
 
-                // Pad the hex_part and text_part if necessary
-                while hex_part.len() < hex_length {
-                    hex_part.push_str("..");
-                    text_part.push(' ');
-                }
+    let files = create_rw_signal(Vec::<LoadedFile>::new());
+    let (active_file, set_active_file) = create_signal(None::<usize>);
+    let next_file_id = store_value(0usize);
+    let (show_upload, set_show_upload) = create_signal(false);
+
+    let (stored_files, set_stored_files) = create_signal(0usize);
+    let (source_url, set_source_url) = create_signal(String::new());
+    let (open_error, set_open_error) = create_signal(None::<String>);
+
+    let current_view = move || FileView {
+        selected_range: selected_range(),
+        selected_line: selected_line(),
+        collapsed_blocks: collapsed_blocks(),
+        selected_function: selected_function(),
+        selected_block: selected_block(),
+        xref_target: xref_target(),
+        annotations: annotations(),
+    };
 
-                // Ensure hex_part and text_part are of consistent length
-                let hex_part_padded = format!("{:width$}", hex_part, width = hex_length);
-                let text_part_padded = format!("{:<width$}", text_part, width = chunk_size);
+    // Stashes the current position into the active file before leaving it
+    let save_view = move || {
+        let Some(id) = active_file.get_untracked() else {
+            return;
+        };
+        let view = untrack(current_view);
+        files.update(|files| {
+            if let Some(file) = files.iter_mut().find(|file| file.id == id) {
+                file.view = view;
+            }
+        });
+    };
 
-                // Format the output string with the current offset
-                format!("{:06x} {} {}", current_offset, hex_part_padded, text_part_padded)
-            })
-            .collect()
-    }
+    // Points the listing, hex dump and panels at `file`, or clears them
+    // Batched so nothing observes one file's data with another file's view
+    let show_file = move |file: Option<&LoadedFile>| {
+        batch(|| {
+            set_active_file(file.map(|file| file.id));
+            set_blob_data(file.map_or_else(Default::default, |file| file.data.clone()));
+            set_disassembled_data(file.map_or_else(Vec::new, |file| file.lines.clone()));
+            set_sections(file.map_or_else(Vec::new, |file| file.sections.clone()));
+            set_symbols(file.map_or_else(ProgramSymbols::default, |file| file.symbols.clone()));
+            set_analysis(file.map_or_else(ProgramAnalysis::default, |file| file.analysis.clone()));
+
+            let view = file.map_or_else(FileView::default, |file| file.view.clone());
+            set_selected_range(view.selected_range);
+            set_selected_line(view.selected_line);
+            set_collapsed_blocks(view.collapsed_blocks);
+            set_selected_function(view.selected_function);
+            set_selected_block(view.selected_block);
+            set_xref_target(view.xref_target);
+            set_annotations(view.annotations);
+        });
+    };
 
+    let switch_to = move |id: usize| {
+        if active_file.get_untracked() == Some(id) {
+            return;
+        }
+        save_view();
+        files.with_untracked(|files| show_file(files.iter().find(|file| file.id == id)));
+    };
 
-    fn serialize_instruction(instruction: &Instruction) -> (String, usize) {
-        let mut buffer = [0u8; 16]; // maximum instruction size?
-        let size = instruction.serialize_into(&mut buffer);
+    let close_file = move |id: usize| {
+        let Some(index) = files.with_untracked(|files| files.iter().position(|file| file.id == id)) else {
+            return;
+        };
+        files.update(|files| {
+            files.remove(index);
+        });
+        if active_file.get_untracked() == Some(id) {
+            // Fall back to the file that took its place in the list, or the one before
+            files.with_untracked(|files| show_file(files.get(index).or_else(|| index.checked_sub(1).and_then(|index| files.get(index)))));
+        }
+    };
 
-        let hex_representation = buffer[..size].iter()
-            .map(|byte| format!("{:02x}", byte))
-            .collect::<Vec<String>>()
-            .join(" ");
+    // Switches to the file with this content if it is open
+    let switch_to_open = move |hash: &str, offset: Option<usize>| -> bool {
+        let Some(id) = files.with_untracked(|files| files.iter().find(|file| file.hash == hash).map(|file| file.id)) else {
+            return false;
+        };
+        switch_to(id);
+        set_show_upload(false);
+        if let Some(offset) = offset {
+            go_to_offset(offset);
+        }
+        true
+    };
 
-        (hex_representation, size)
-    }
+    // Opens a blob as a new file, or switches to it when the same content is
+    // already open. Blobs are stored for the recent files list, and the view
+    // last used for the same content is restored unless `offset` asks for a
+    // specific place.
+    let open_blob = move |filename: String, data: Vec<u8>, source: Option<String>, offset: Option<usize>| {
+        let hash = storage::content_hash(&data);
+        if switch_to_open(&hash, offset) {
+            return;
+        }
 
-    fn find_first_instruction_binary(blob: &ProgramBlob) -> Result<Vec<u8>, String> {
-        let first_instruction = blob.instructions().next()
-            .ok_or("No instructions in the blob")?
-            .map_err(|e| format!("Error parsing first instruction: {}", e))?;
+        spawn_local(async move {
+            let view = load_state::<FileView>(&FileView::storage_key(&hash)).await.unwrap_or_else(|error| {
+                log::warn!("{}", error);
+                None
+            });
+            match save_file(&hash, &filename, &data).await {
+                Ok(()) => set_stored_files.update(|stored| *stored += 1),
+                Err(error) => log::warn!("{}", error),
+            }
+            // The same blob may have been opened while this one was loading,
+            // e.g. when it was dropped twice at once
+            if switch_to_open(&hash, offset) {
+                return;
+            }
 
-        let (binary, _) = serialize_instruction(&first_instruction);
+            let id = next_file_id.get_value();
+            next_file_id.set_value(id + 1);
+            let source = source.unwrap_or_else(|| hash.clone());
+            let mut file = analyze_file(id, hash, source, filename, &data);
+            file.view = view.unwrap_or_default();
+            save_view();
+            add_file(files, file, show_file);
+            set_show_upload(false);
+            if let Some(offset) = offset {
+                go_to_offset(offset);
+            }
+        });
+    };
 
-        // Convert the binary string to a byte array
-        let mut result = Vec::new();
-        for byte_str in binary.split_whitespace() {
-            let byte = u8::from_str_radix(byte_str, 16)
-                .map_err(|e| format!("Error parsing byte: {}", e))?;
-            result.push(byte);
+    // Remembers the position in the active file across reloads
+    create_effect(move |_| {
+        let view = current_view();
+        let Some(id) = active_file() else {
+            return;
+        };
+        let Some(hash) = files.with_untracked(|files| files.iter().find(|file| file.id == id).map(|file| file.hash.clone())) else {
+            return;
+        };
+        spawn_local(async move {
+            if let Err(error) = save_state(&FileView::storage_key(&hash), &view).await {
+                log::warn!("{}", error);
+            }
+        });
+    });
+
+    // Opens a stored file by content hash, or downloads one from a URL
+    let open_source = move |source: String, offset: Option<usize>| {
+        set_open_error(None);
+        async move {
+            let result = if storage::is_content_hash(&source) {
+                load_file(&source)
+                    .await
+                    .and_then(|stored| stored.ok_or_else(|| format!("No stored file with hash {}", source)))
+                    .map(|(stored, data)| (stored.filename, data))
+            } else {
+                fetch_blob(&source).await.map(|data| (url_filename(&source), data))
+            };
+            match result {
+                Ok((filename, data)) => open_blob(filename, data, Some(source), offset),
+                Err(error) => {
+                    set_open_error(Some(error));
+                    set_show_upload(true);
+                }
+            }
         }
+    };
 
-        Ok(result)
-    }
+    // Deep links: `?src=<content hash or URL>#<offset>` opens a file, e.g.
+    // from the recent files list or the sample gallery, and selects the
+    // offset. The URL follows the active file and selection in turn.
+    let query = use_query_map();
+    let location = use_location();
+    let navigate = use_navigate();
+    // Last link written from the selection, which needs no handling when the
+    // router reports it back
+    let written_link = store_value(None::<(String, String)>);
+
+    create_effect(move |_| {
+        let source = query.with(|query| query.get("src").cloned());
+        let fragment = location.hash.with(|hash| hash.trim_start_matches('#').to_string());
+        let Some(source) = source else {
+            return;
+        };
+        if written_link.with_value(|written| written.as_ref() == Some(&(source.clone(), fragment.clone()))) {
+            return;
+        }
 
-    // Function to find the true byte offset of the first instruction in the blob
-    fn find_true_byte_offset(blob: &ProgramBlob, first_instruction_binary: &[u8]) -> Result<usize, String> {
-        let blob_bytes = blob.as_bytes();
+        let offset = parse_number(&fragment).map(|offset| offset as usize);
+        let open = files.with_untracked(|files| {
+            files
+                .iter()
+                .find(|file| file.source == source || file.hash == source)
+                .map(|file| file.id)
+        });
+        match open {
+            Some(id) => {
+                switch_to(id);
+                if let Some(offset) = offset {
+                    go_to_offset(offset);
+                }
+            }
+            None => spawn_local(open_source(source, offset)),
+        }
+    });
+
+    create_effect(move |had_file: Option<bool>| {
+        let selected = selected_line();
+        let Some(id) = active_file() else {
+            if had_file == Some(true) {
+                written_link.set_value(None);
+                navigate("/disassembler", NavigateOptions { replace: true, scroll: false, ..Default::default() });
+            }
+            return false;
+        };
+        let Some((source, fragment)) = files.with_untracked(|files| link_target(files, id, selected.as_deref())) else {
+            return true;
+        };
+
+        let mut link = format!("/disassembler?src={}", String::from(js_sys::encode_uri_component(&source)));
+        if !fragment.is_empty() {
+            link.push('#');
+            link.push_str(&fragment);
+        }
+        written_link.set_value(Some((source, fragment)));
+        navigate(&link, NavigateOptions { replace: true, scroll: false, ..Default::default() });
+        true
+    });
+
+    let unload_all = move || {
+        files.set(Vec::new());
+        show_file(None);
+        set_show_upload(false);
+    };
 
-        blob_bytes.windows(first_instruction_binary.len())
-            .position(|window| window == first_instruction_binary)
-            .ok_or_else(|| "Binary sequence not found".to_string())
-    }
+    let (show_settings, set_show_settings) = create_signal(false);
 
-    // Updated calculate_program_base_address function
-    fn calculate_program_base_address(blob: &ProgramBlob) -> Result<usize, String> {
-        let first_instruction_binary = find_first_instruction_binary(blob)?;
-        println!("First instruction binary: {:?}", first_instruction_binary);
-        find_true_byte_offset(blob, &first_instruction_binary)
+    let appearance = use_appearance();
+    let zoom = move || format!("{}%", appearance().zoom);
 
-    }
+    let menu = MainMenu::disassembler().expect("public/ron/disassembler.ron is a valid menu");
+    let menu_actions = menu_actions(set_show_upload, set_show_settings, unload_all, appearance);
 
-    fn disassemble_into_lines(data: &[u8]) -> Result<Vec<DisassembledLine>, &'static str> {
-        let blob = ProgramBlob::parse(data).map_err(|_| "Failed to parse blob")?;
-
-        // Assuming you have a function that calculates the true base address of the program
-        let program_base_address = calculate_program_base_address(&blob)
-            .map_err(|_| "Failed to calculate program base address")?;
-
-        let mut result = Vec::new();
-        let mut current_byte_offset: usize = 0; // Start from the calculated program base address
-
-        for maybe_instruction in blob.instructions() {
-            match maybe_instruction {
-                Ok(instruction) => {
-                    let mut serialized = [0u8; 32]; // Adjust size as needed
-                    let size = instruction.serialize_into(&mut serialized);
-                    let hex_buffer = serialized[..size]
-                        .iter()
-                        .fold(String::new(), |mut acc, byte| {
-                            use std::fmt::Write;
-                            write!(acc, "{:02X} ", byte).expect("Failed to write to String");
-                            acc
-                        });
-
-                    // Extract the opcode name from the instruction
-                    let opcode_name = format!("{:?}", instruction.opcode());
-
-                    // Calculate the true offset for this instruction
-                    let true_offset = current_byte_offset + program_base_address;
-
-                    result.push(DisassembledLine::new(
-                        true_offset,
-                        hex_buffer,
-                        instruction.to_string(),
-                        opcode_name, // Pass the opcode name to the operation field
-                    ));
-                    current_byte_offset += size;
-                },
-                Err(error) => {
-                    // Even in case of an error, calculate the true offset
-                    let true_offset = current_byte_offset + program_base_address;
-
-                    result.push(DisassembledLine::new(
-                        true_offset,
-                        "ERROR".to_string(),
-                        format!("Error: {}", error),
-                        "Unknown".to_string(), // Use a placeholder like "Unknown" for errors
-                    ));
-                }
-            }
-        }
-        Ok(result)
-    }
 
     view! {
         <div class="flex flex-col">
             <div class="flex h-16 w-full items-center px-4 md:px-6 bg-gray-100 dark:bg-gray-800">
                 <div>
-                    <MainMenu/>
+                    <MenuBar menu=menu actions=menu_actions/>
                 </div>
             </div>
+            <Show when=show_settings>
+                <SettingsDialog on_close=move || set_show_settings(false)/>
+            </Show>
             <div class="flex flex-1 overflow-auto">
-                <Show when=move || !unified_data().is_empty()>
+                <Show when=move || files.with(|files| !files.is_empty())>
                     <aside class="w-32 md:w-40 lg:w-48 xl:w-64 bg-gray-200 dark:bg-gray-700 p-2 lg:p-4 overflow-auto">
-                        <nav class="p-2 lg:p-4 bg-gray-100 w-full shadow-md">
-                            <a
-                                class="flex items-center text-sm hover:bg-gray-200 p-1"
-                                href="#"
-                                rel="ugc"
-                                on:click=move |_| {
-                                    set_show_file_options(!show_file_options.get());
-                                }
-                            >
-
-                                <div class="i-mdi-file" /><span class="truncate">{filename}</span>
-                            </a>
-                            <Show when=move || show_file_options.get()>
-                                <ul class="list-none p-0 m-0 text-xs">
-                                    <li
-                                        class="cursor-pointer hover:bg-gray-300 p-2 rounded overflow-auto"
-                                        on:click=move |_| {
-                                            set_unified_data(Vec::new());
-                                            set_chunk_size(0);
-                                            set_filename(String::new());
-                                            set_disassembled_data(Vec::new());
+                        <nav class="p-2 lg:p-4 bg-gray-100 w-full shadow-md dark:bg-gray-800">
+                            {move || files.with(|files| {
+                                files
+                                    .iter()
+                                    .map(|file| {
+                                        let id = file.id;
+                                        let error = file.error.clone();
+                                        view! {
+                                            <div
+                                                class="flex items-center text-sm hover:bg-gray-200 p-1 dark:hover:bg-gray-600"
+                                                class:bg-gray-300=move || active_file() == Some(id)
+                                            >
+                                                <a
+                                                    class="flex flex-1 items-center min-w-0"
+                                                    href="#"
+                                                    rel="ugc"
+                                                    title=file.filename.clone()
+                                                    on:click=move |event| {
+                                                        event.prevent_default();
+                                                        switch_to(id);
+                                                    }
+                                                >
+                                                    {match error {
+                                                        Some(error) => view! { <div class="i-mdi-alert-circle text-red-600" title=error /> },
+                                                        None => view! { <div class="i-mdi-file" /> },
+                                                    }}
+                                                    <span class="truncate">{file.filename.clone()}</span>
+                                                </a>
+                                                <button
+                                                    class="ml-1 text-gray-500 hover:text-gray-900 dark:hover:text-gray-100"
+                                                    title="Close"
+                                                    on:click=move |_| close_file(id)
+                                                >
+                                                    <div class="i-mdi-close" />
+                                                </button>
+                                            </div>
                                         }
-                                    >
-
-                                        <a><div class="i-mdi-remove" />remove</a>
-                                    </li>
-                                </ul>
-                            </Show>
+                                    })
+                                    .collect::<Vec<_>>()
+                            })}
                         </nav>
+                        <Show when=move || !symbols().is_empty()>
+                            <SymbolTable
+                                symbols=symbols
+                                on_export_selected=move |export: ExportSymbol| {
+                                    let function_index = analysis.with_untracked(|analysis| {
+                                        analysis.block_function.get(&export.jump_target).copied()
+                                    });
+                                    set_selected_function(function_index);
+                                    select_block(export.jump_target);
+                                }
+                            />
+                        </Show>
+                        <Show when=move || xref_target().is_some()>
+                            <XRefPanel
+                                analysis=analysis
+                                target=xref_target
+                                on_xref_selected=select_instruction
+                                on_close=move || set_xref_target(None)
+                            />
+                        </Show>
                     </aside>
                 </Show>
                 <div class="flex flex-1 overflow-auto">
                     <div class="w-full h-full">
                         <div class="h-60vh flex flex-row p-4">
-                            <Show when=move || unified_data().is_empty()>
+                            <Show when=move || unified_data().is_empty() || show_upload()>
                                 <div class="border-dashed border-4 w-full h-full p-4">
-                                    <FileUploadComponent on_file_uploaded=move |
+                                    <FileUploadComponent multiple=true on_file_uploaded=move |
                                         data_option,
                                         filename|
                                     {
                                         if let Some(data) = data_option {
-                                            set_filename(filename);
-                                            set_chunk_size(16);
-                                            set_unified_data(
-                                                unified_representation(&data, chunk_size.get() as usize),
-                                            );
-
-                                            match disassemble_into_lines(&data) {
-                                                Ok(disassembled) => set_disassembled_data(disassembled),
-                                                Err(error) => {
-                                                    set_disassembled_data(Vec::new());
-                                                    println!("{}", error);
-                                                }
-                                            }
+                                            open_blob(filename, data, None, None);
                                         }
                                     }/>
+                                    <form
+                                        class="flex items-center gap-2 mt-2 text-sm"
+                                        on:submit=move |event| {
+                                            event.prevent_default();
+                                            let url = source_url.get_untracked().trim().to_string();
+                                            if !url.is_empty() {
+                                                spawn_local(open_source(url, None));
+                                            }
+                                        }
+                                    >
+                                        <input
+                                            type="url"
+                                            class="flex-1 h-8 px-2 rounded-sm border border-gray-300 dark:bg-gray-900 dark:border-gray-600"
+                                            placeholder="https://example.com/program.polkavm"
+                                            prop:value=source_url
+                                            on:input=move |event| set_source_url(event_target_value(&event))
+                                        />
+                                        <button
+                                            type="submit"
+                                            class="px-3 py-1 font-semibold text-gray-700 bg-white hover:bg-gray-100 rounded-xs border border-gray-300 dark:bg-gray-900 dark:hover:bg-gray-700 dark:border-gray-600 dark:text-gray-200"
+                                        >
+                                            "Open from URL"
+                                        </button>
+                                    </form>
+                                    {move || open_error().map(|error| view! { <div class="mt-1 text-sm text-red-600">{error}</div> })}
+                                    <RecentFiles refresh=stored_files/>
+                                    <Show when=move || !unified_data().is_empty()>
+                                        <button
+                                            class="mt-2 px-3 py-1 text-sm font-semibold text-gray-700 bg-white hover:bg-gray-100 rounded-xs border border-gray-300 dark:bg-gray-900 dark:hover:bg-gray-700 dark:border-gray-600 dark:text-gray-200"
+                                            on:click=move |_| set_show_upload(false)
+                                        >
+                                            "Cancel"
+                                        </button>
+                                    </Show>
                                 </div>
                             </Show>
-                            <Show when=move || !unified_data().is_empty()>
-                                <pre class="border w-full h-full border-gray-200 rounded p-2 bg-gray-100 overflow-x-scroll">
+                            <Show when=move || !unified_data().is_empty() && !show_upload()>
+                                <pre class="border w-full h-full border-gray-200 rounded p-2 bg-gray-100 overflow-x-scroll dark:bg-gray-800 dark:border-gray-700" style:zoom=zoom>
                                     {move || {
+                                        let row_size = chunk_size.get();
                                         unified_data()
                                             .iter()
-                                            .map(|line| {
+                                            .enumerate()
+                                            .map(|(index, line)| {
+                                                let row_start = index * row_size;
+                                                let is_selected = move || {
+                                                    selected_range.with(|range| {
+                                                        range.as_ref().map_or(false, |range| row_start < range.end && range.start < row_start + row_size)
+                                                    })
+                                                };
                                                 view! {
-                                                    <div class="py-1 font-mono text-xs md:text-md xl:text-lg">
+                                                    <div
+                                                        id=format!("hex-row-{}", index)
+                                                        class="py-1 font-mono text-xs md:text-md xl:text-lg"
+                                                        class:bg-yellow-100=is_selected
+                                                    >
                                                         {line}
                                                     </div>
                                                 }
@@ -381,6 +708,18 @@ pub fn Disassembler() -> impl IntoView {
                                     }}
 
                                 </pre>
+                                <div class="w-1/3 h-full ml-4">
+                                    <BlobStructure
+                                        sections=sections
+                                        on_section_selected=move |section: BlobSection| {
+                                            let row_size = chunk_size.get_untracked();
+                                            if row_size > 0 {
+                                                scroll_into_view(&format!("hex-row-{}", section.range.start / row_size));
+                                            }
+                                            set_selected_range(Some(section.range));
+                                        }
+                                    />
+                                </div>
                             </Show>
                         </div>
                         <header class="flex h-16 w-full items-center px-4 md:px-6 bg-gray-100 dark:bg-gray-800">
@@ -406,37 +745,154 @@ pub fn Disassembler() -> impl IntoView {
                                     Disassembler
                                 </button>
                             </div>
+                            <select
+                                class="ml-4 h-8 px-2 text-sm rounded-sm border border-gray-300 bg-white dark:bg-gray-900 dark:border-gray-600"
+                                on:change=move |event| {
+                                    let function_index = event_target_value(&event).parse::<usize>().ok();
+                                    set_selected_function(function_index);
+                                    let entry = function_index.and_then(|index| {
+                                        analysis.with_untracked(|analysis| analysis.functions.get(index).map(|function| function.entry))
+                                    });
+                                    if let Some(entry) = entry {
+                                        select_block(entry);
+                                    }
+                                }
+                            >
+                                <option value="" selected=move || selected_function().is_none()>"Control flow graph: none"</option>
+                                {move || analysis.with(|analysis| {
+                                    analysis
+                                        .functions
+                                        .iter()
+                                        .enumerate()
+                                        .map(|(index, function)| {
+                                            view! {
+                                                <option value=index.to_string() selected=move || selected_function() == Some(index)>
+                                                    {format!("fn {}", function.name)}
+                                                </option>
+                                            }
+                                        })
+                                        .collect::<Vec<_>>()
+                                })}
+                            </select>
                         </header>
 
-                        <div class="w-full mt-4 border-t border-gray-200 dark:border-gray-800 overflow-x-auto">
-                            {/* flex container for headers */}
-                            <div class="flex divide-x divide-gray-200">
-                                <div class="flex-1 p-2 font-bold text-left bg-gray-200">"Offset"</div>
-                                <div class="flex-1 p-2 font-bold text-left bg-gray-200">"Hex"</div>
-                                <div class="flex-1 p-2 font-bold text-left bg-gray-200">"Assembly"</div>
-                                <div class="flex-1 p-2 font-bold text-left bg-gray-200">"Operation"</div>
-                            </div>
+                        <div class="flex flex-row w-full">
+                            <div class=move || {
+                                let width = if selected_function().is_some() { "w-1/2" } else { "w-full" };
+                                format!("{} mt-4 border-t border-gray-200 dark:border-gray-800 overflow-x-auto", width)
+                            } style:zoom=zoom>
+                                {/* flex container for headers */}
+                                <div class="flex divide-x divide-gray-200 dark:divide-gray-700">
+                                    {move || {
+                                        let columns = columns();
+                                        [
+                                            ("Offset", columns.offset),
+                                            ("Hex", columns.hex),
+                                            ("Assembly", columns.assembly),
+                                            ("Operation", columns.operation),
+                                        ]
+                                        .into_iter()
+                                        .filter(|&(_, shown)| shown)
+                                        .map(|(title, _)| view! {
+                                            <div class="flex-1 p-2 font-bold text-left bg-gray-200 dark:bg-gray-700">{title}</div>
+                                        })
+                                        .collect::<Vec<_>>()
+                                    }}
+                                </div>
 
-                            {/* Flex container for content */}
-                            <Show when=move || !disassembled_data().is_empty()>
-                                {move || disassembled_data().iter().map(|line| {
-                                    view! {
-                                        <div class="flex divide-x divide-gray-200">
-                                            <div class="flex-1 p-2 bg-white">
-                                                <pre class="whitespace-pre-wrap overflow-x-auto">{&line.offset}</pre>
-                                            </div>
-                                            <div class="flex-1 p-2 bg-white">
-                                                <pre class="whitespace-pre-wrap overflow-x-auto">{&line.hex}</pre>
-                                            </div>
-                                            <div class="flex-1 p-2 bg-white">
-                                                <pre class="whitespace-pre-wrap overflow-x-auto">{&line.assembly}</pre>
-                                            </div>
-                                            <div class="flex-1 p-2 bg-white">
-                                                <pre class="whitespace-pre-wrap overflow-x-auto">{&line.operation}</pre>
-                                            </div>
-                                        </div>
-                                    }
-                                }).collect::<Vec<_>>()}
+                                {/* Flex container for content */}
+                                <Show when=move || !disassembled_data().is_empty()>
+                                    {move || {
+                                        let lines = disassembled_data();
+                                        let analysis = analysis();
+                                        let collapsed = collapsed_blocks();
+                                        let settings = settings();
+                                        let cost_model = cost_model();
+                                        let annotations = annotations();
+                                        let row = |(index, line): (usize, &DisassembledLine)| {
+                                            // Only block starts can be referenced, so right-clicking
+                                            // anywhere in a block shows the references to the block
+                                            let jump_target = analysis.block_of_instruction(index).map(|block| block.jump_target);
+                                            let offset = line.offset.clone();
+                                            let selected_offset = line.offset.clone();
+                                            let address = line.address;
+                                            listing_row(
+                                                line,
+                                                settings,
+                                                move || selected_line.with(|selected| selected.as_ref() == Some(&selected_offset)),
+                                                annotations.get(&address).cloned(),
+                                                move || set_selected_line(Some(offset.clone())),
+                                                move || annotate(address),
+                                                move || {
+                                                    if jump_target.is_some() {
+                                                        set_xref_target(jump_target);
+                                                    }
+                                                },
+                                            )
+                                        };
+
+                                        let mut rows = Vec::new();
+                                        for block in &analysis.blocks {
+                                            let jump_target = block.jump_target;
+                                            let function = analysis.function_of_block(jump_target);
+                                            if let Some(function) = function.filter(|function| function.entry == jump_target) {
+                                                rows.push(view! {
+                                                    <div class="p-2 mt-2 font-mono font-bold bg-gray-300 border-t-2 border-gray-400 dark:bg-gray-600 dark:border-gray-500">
+                                                        {format!("fn {}", function.name)}
+                                                    </div>
+                                                }.into_view());
+                                            }
+
+                                            let is_collapsed = collapsed.contains(&jump_target);
+                                            let instruction_count = block.instructions.len();
+                                            let gas_cost = block_cost(cost_model.as_ref(), &analysis, block);
+                                            rows.push(view! {
+                                                <div
+                                                    class="flex items-center p-1 font-mono text-sm text-gray-600 bg-gray-100 border-t border-gray-300 cursor-pointer hover:bg-gray-200 dark:bg-gray-800 dark:hover:bg-gray-600 dark:border-gray-600 dark:text-gray-300"
+                                                    on:click=move |_| {
+                                                        set_collapsed_blocks.update(|collapsed| {
+                                                            if !collapsed.remove(&jump_target) {
+                                                                collapsed.insert(jump_target);
+                                                            }
+                                                        });
+                                                    }
+                                                >
+                                                    <div class={if is_collapsed { "i-mdi-chevron-right" } else { "i-mdi-chevron-down" }} />
+                                                    {format!("@{}:", jump_target)}
+                                                    <span class="ml-2 text-xs">
+                                                        {format!("{} instructions, {} gas", instruction_count, gas_cost)}
+                                                    </span>
+                                                </div>
+                                            }.into_view());
+
+                                            if !is_collapsed {
+                                                rows.extend(
+                                                    lines
+                                                        .get(block.instructions.clone())
+                                                        .unwrap_or_default()
+                                                        .iter()
+                                                        .enumerate()
+                                                        .map(|(offset, line)| row((block.instructions.start + offset, line))),
+                                                );
+                                            }
+                                        }
+
+                                        // Lines past the analyzed code, e.g. a parse error
+                                        let analyzed_count = analysis.blocks.last().map_or(0, |block| block.instructions.end);
+                                        rows.extend(lines.iter().enumerate().skip(analyzed_count).map(row));
+                                        rows
+                                    }}
+                                </Show>
+                            </div>
+                            <Show when=move || selected_function().is_some()>
+                                <div class="w-1/2 h-80vh mt-4 ml-2 sticky top-0">
+                                    <ControlFlowGraph
+                                        analysis=analysis
+                                        function=selected_function
+                                        selected_block=selected_block
+                                        on_block_selected=select_block
+                                    />
+                                </div>
                             </Show>
                         </div>
                     </div>
@@ -445,3 +901,49 @@ pub fn Disassembler() -> impl IntoView {
         </div>
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_menu_action_has_a_handler() {
+        let runtime = create_runtime();
+        let (_, set_show_upload) = create_signal(false);
+        let (_, set_show_settings) = create_signal(false);
+        let actions = menu_actions(set_show_upload, set_show_settings, || {}, create_rw_signal(Appearance::default()));
+
+        let menu = MainMenu::disassembler().expect("public/ron/disassembler.ron is a valid menu");
+        assert_eq!(actions.check(&menu), Ok(()));
+        runtime.dispose();
+    }
+
+    #[test]
+    fn link_follows_a_newly_opened_file() {
+        let runtime = create_runtime();
+        let files = create_rw_signal(Vec::new());
+        let (active_file, set_active_file) = create_signal(None::<usize>);
+        let (selected_line, set_selected_line) = create_signal(None::<String>);
+        let written = create_rw_signal(None::<(String, String)>);
+        // Like the URL sync, which only tracks the active file and selection
+        create_effect(move |_| {
+            let selected = selected_line();
+            if let Some(id) = active_file() {
+                written.set(files.with_untracked(|files| link_target(files, id, selected.as_deref())));
+            }
+        });
+
+        let source = "https://example.com/hello.polkavm".to_string();
+        let mut file = analyze_file(3, "hash".to_string(), source.clone(), "hello.polkavm".to_string(), &[]);
+        file.view.selected_line = Some("00001A".to_string());
+        add_file(files, file, |file| {
+            batch(|| {
+                set_active_file(file.map(|file| file.id));
+                set_selected_line(file.and_then(|file| file.view.selected_line.clone()));
+            })
+        });
+
+        assert_eq!(written.get_untracked(), Some((source, "0x00001A".to_string())));
+        runtime.dispose();
+    }
+}
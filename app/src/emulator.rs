@@ -0,0 +1,862 @@
+use leptos::*;
+use polkavm_common::program::Reg;
+use std::rc::Rc;
+use crate::disassembler::scroll_into_view;
+use crate::file_upload::FileUploadComponent;
+use crate::breakpoints::{parse_number, Breakpoints, Condition, Watchpoint};
+use crate::gas::{block_cost, use_cost_model, CostModel, CostTable, GasMeter, UnitCost};
+use crate::host::{HostBehavior, HostConfig};
+use crate::interpreter::{Interpreter, Program, Status, TrapReason};
+use crate::memory_view::MemoryInspector;
+use crate::trace_view::TraceView;
+use crate::coverage::heat_level;
+use crate::history::{History, WriteTarget};
+use crate::coverage_view::{heat_class, HotSpotPanel};
+use crate::storage;
+use crate::database::{load_state, save_state};
+
+fn instruction_element_id(index: usize) -> String {
+    format!("emu-{}", index)
+}
+
+#[component]
+fn Registers(interpreter: RwSignal<Option<Interpreter>>) -> impl IntoView {
+    view! {
+        <table class="w-full font-mono text-sm">
+            <thead>
+                <tr class="text-left bg-gray-200">
+                    <th class="p-1">"Reg"</th>
+                    <th class="p-1">"Hex"</th>
+                    <th class="p-1">"Decimal"</th>
+                </tr>
+            </thead>
+            <tbody>
+                {Reg::ALL
+                    .into_iter()
+                    .map(|reg| {
+                        let value = move || interpreter.with(|vm| vm.as_ref().map_or(0, |vm| vm.reg(reg)));
+                        view! {
+                            <tr class="border-t border-gray-200">
+                                <td class="p-1 font-bold">{reg.to_string()}</td>
+                                <td class="p-1">{move || format!("0x{:08x}", value())}</td>
+                                <td class="p-1">{move || (value() as i32).to_string()}</td>
+                            </tr>
+                        }
+                    })
+                    .collect::<Vec<_>>()}
+            </tbody>
+        </table>
+    }
+}
+
+#[component]
+fn BreakpointPanel(breakpoints: RwSignal<Breakpoints>, program: ReadSignal<Option<Rc<Program>>>) -> impl IntoView {
+    let (condition_error, set_condition_error) = create_signal(None::<String>);
+    let (watch_address, set_watch_address) = create_signal(String::new());
+    let (watch_length, set_watch_length) = create_signal(String::from("4"));
+    let (watch_read, set_watch_read) = create_signal(false);
+    let (watch_write, set_watch_write) = create_signal(true);
+
+    let add_watchpoint = move |_| {
+        let (Some(address), Some(length)) = (parse_number(&watch_address()), parse_number(&watch_length())) else {
+            set_condition_error(Some("Invalid watchpoint address or length".to_string()));
+            return;
+        };
+        set_condition_error(None);
+        breakpoints.update(|breakpoints| {
+            breakpoints.watchpoints.push(Watchpoint {
+                address,
+                length: length.max(1),
+                on_read: watch_read(),
+                on_write: watch_write(),
+                enabled: true,
+            })
+        });
+    };
+
+    view! {
+        <div class="p-2 font-bold bg-gray-200">"Breakpoints"</div>
+        <div class="p-2 text-xs text-gray-600">"Click the gutter of the listing to toggle a breakpoint."</div>
+        {move || condition_error().map(|message| view! { <div class="px-2 text-xs text-red-600">{message}</div> })}
+        <ul class="list-none p-0 m-0 text-xs font-mono">
+            {move || {
+                breakpoints()
+                    .breakpoints
+                    .into_iter()
+                    .enumerate()
+                    .map(|(position, breakpoint)| {
+                        let location = program
+                            .with_untracked(|program| {
+                                program.as_ref().and_then(|program| {
+                                    let analysis = &program.analysis;
+                                    analysis.instructions.get(breakpoint.instruction).map(|analyzed| {
+                                        format!("{:06X} {}", analysis.code_offset + analyzed.offset, analyzed.instruction)
+                                    })
+                                })
+                            })
+                            .unwrap_or_else(|| format!("#{}", breakpoint.instruction));
+                        view! {
+                            <li class="flex items-center gap-1 px-2 py-1 border-t border-gray-200">
+                                <input
+                                    type="checkbox"
+                                    prop:checked=breakpoint.enabled
+                                    on:change=move |event| {
+                                        let enabled = event_target_checked(&event);
+                                        breakpoints.update(|breakpoints| breakpoints.breakpoints[position].enabled = enabled);
+                                    }
+                                />
+                                <span class="flex-1 truncate" title=location.clone()>{location.clone()}</span>
+                                <input
+                                    type="text"
+                                    class="w-24 px-1 border border-gray-300"
+                                    placeholder="a0 == 0x10"
+                                    prop:value=breakpoint.condition.map(|condition| condition.to_string()).unwrap_or_default()
+                                    on:change=move |event| {
+                                        let text = event_target_value(&event);
+                                        let condition = if text.trim().is_empty() {
+                                            Ok(None)
+                                        } else {
+                                            text.parse::<Condition>().map(Some)
+                                        };
+                                        match condition {
+                                            Ok(condition) => {
+                                                set_condition_error(None);
+                                                breakpoints.update(|breakpoints| breakpoints.breakpoints[position].condition = condition);
+                                            }
+                                            Err(message) => set_condition_error(Some(message)),
+                                        }
+                                    }
+                                />
+                                <span
+                                    class="cursor-pointer"
+                                    on:click=move |_| breakpoints.update(|breakpoints| {
+                                        breakpoints.breakpoints.remove(position);
+                                    })
+                                >
+                                    <div class="i-mdi-close" />
+                                </span>
+                            </li>
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            }}
+        </ul>
+        <div class="p-2 font-bold bg-gray-200">"Watchpoints"</div>
+        <ul class="list-none p-0 m-0 text-xs font-mono">
+            {move || {
+                breakpoints()
+                    .watchpoints
+                    .into_iter()
+                    .enumerate()
+                    .map(|(position, watchpoint)| {
+                        view! {
+                            <li class="flex items-center gap-1 px-2 py-1 border-t border-gray-200">
+                                <input
+                                    type="checkbox"
+                                    prop:checked=watchpoint.enabled
+                                    on:change=move |event| {
+                                        let enabled = event_target_checked(&event);
+                                        breakpoints.update(|breakpoints| breakpoints.watchpoints[position].enabled = enabled);
+                                    }
+                                />
+                                <span class="flex-1">{watchpoint.to_string()}</span>
+                                <span
+                                    class="cursor-pointer"
+                                    on:click=move |_| breakpoints.update(|breakpoints| {
+                                        breakpoints.watchpoints.remove(position);
+                                    })
+                                >
+                                    <div class="i-mdi-close" />
+                                </span>
+                            </li>
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            }}
+        </ul>
+        <div class="flex flex-wrap items-center gap-1 p-2 text-xs">
+            <input
+                type="text"
+                class="w-24 px-1 border border-gray-300 font-mono"
+                placeholder="0x20000"
+                prop:value=watch_address
+                on:input=move |event| set_watch_address(event_target_value(&event))
+            />
+            <input
+                type="text"
+                class="w-12 px-1 border border-gray-300 font-mono"
+                placeholder="length"
+                prop:value=watch_length
+                on:input=move |event| set_watch_length(event_target_value(&event))
+            />
+            <label>
+                <input type="checkbox" prop:checked=watch_read on:change=move |event| set_watch_read(event_target_checked(&event))/>
+                "read"
+            </label>
+            <label>
+                <input type="checkbox" prop:checked=watch_write on:change=move |event| set_watch_write(event_target_checked(&event))/>
+                "write"
+            </label>
+            <button class="px-2 border border-gray-300 bg-white hover:bg-gray-100" on:click=add_watchpoint>"Add"</button>
+        </div>
+    }
+}
+
+#[component]
+fn HostPanel(host_config: RwSignal<HostConfig>, program: ReadSignal<Option<Rc<Program>>>) -> impl IntoView {
+    let (config_error, set_config_error) = create_signal(None::<String>);
+
+    view! {
+        <div class="p-2 font-bold bg-gray-200">"Host functions"</div>
+        <div class="p-2 text-xs border-dashed border-2 m-2">
+            <FileUploadComponent
+                accept=".ron,.toml"
+                on_file_uploaded=move |data_option, filename| {
+                    let Some(data) = data_option else {
+                        return;
+                    };
+                    let parsed = String::from_utf8(data)
+                        .map_err(|_| "Host config is not valid UTF-8".to_string())
+                        .and_then(|contents| HostConfig::parse(&filename, &contents));
+                    match parsed {
+                        Ok(config) => {
+                            set_config_error(None);
+                            host_config.set(config);
+                        }
+                        Err(message) => set_config_error(Some(message)),
+                    }
+                }
+            />
+        </div>
+        {move || config_error().map(|message| view! { <div class="px-2 text-xs text-red-600">{message}</div> })}
+        <table class="w-full text-xs font-mono">
+            <tbody>
+                {move || {
+                    let imports = program().map(|program| program.imports.clone()).unwrap_or_default();
+                    imports
+                        .into_iter()
+                        .enumerate()
+                        .map(|(index, import)| {
+                            let import = store_value(import);
+                            let behavior = move || host_config.with(|config| import.with_value(|import| config.behavior(import).clone()));
+                            let set_behavior = move |behavior: HostBehavior| {
+                                host_config.update(|config| import.with_value(|import| config.set_behavior(import, behavior)));
+                            };
+                            let name = import.get_value();
+                            view! {
+                                <tr class="border-t border-gray-200">
+                                    <td class="p-1">{format!("#{}", index)}</td>
+                                    <td class="p-1 truncate" title=name.clone()>{name}</td>
+                                    <td class="p-1">
+                                        <select
+                                            class="border border-gray-300 bg-white"
+                                            on:change=move |event| set_behavior(behavior().with_kind(&event_target_value(&event)))
+                                        >
+                                            {HostBehavior::KINDS
+                                                .into_iter()
+                                                .map(|option| view! { <option value=option prop:selected=move || behavior().kind() == option>{option}</option> })
+                                                .collect::<Vec<_>>()}
+                                        </select>
+                                    </td>
+                                    <td class="p-1">
+                                        <Show when=move || behavior().kind() == "return">
+                                            <input
+                                                type="text"
+                                                class="w-20 px-1 border border-gray-300"
+                                                prop:value=move || behavior().constant().to_string()
+                                                on:change=move |event| {
+                                                    if let Some(value) = parse_number(&event_target_value(&event)) {
+                                                        set_behavior(HostBehavior::Return(value));
+                                                    }
+                                                }
+                                            />
+                                        </Show>
+                                    </td>
+                                </tr>
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                }}
+            </tbody>
+        </table>
+    }
+}
+
+#[component]
+fn HostCallLog(interpreter: RwSignal<Option<Interpreter>>) -> impl IntoView {
+    let output = move || interpreter.with(|vm| vm.as_ref().map(|vm| vm.host().output.clone()).unwrap_or_default());
+
+    view! {
+        <div class="w-1/2 h-40vh overflow-auto border border-gray-200 rounded bg-gray-50">
+            <div class="p-2 font-bold bg-gray-200">"Host calls"</div>
+            <table class="w-full text-xs font-mono">
+                <thead>
+                    <tr class="text-left bg-gray-100">
+                        <th class="p-1">"Import"</th>
+                        <th class="p-1">"Arguments (a0..a5)"</th>
+                        <th class="p-1">"Result"</th>
+                    </tr>
+                </thead>
+                <tbody>
+                    {move || {
+                        interpreter.with(|vm| {
+                            vm.as_ref()
+                                .map(|vm| {
+                                    vm.host()
+                                        .calls
+                                        .iter()
+                                        .map(|call| {
+                                            let args = call
+                                                .args
+                                                .iter()
+                                                .map(|arg| format!("0x{:x}", arg))
+                                                .collect::<Vec<_>>()
+                                                .join(", ");
+                                            let result = call.result.map_or_else(|| "trap".to_string(), |value| format!("0x{:x}", value));
+                                            view! {
+                                                <tr class="border-t border-gray-200">
+                                                    <td class="p-1">{call.name.clone()}</td>
+                                                    <td class="p-1">{args}</td>
+                                                    <td class="p-1">{result}</td>
+                                                </tr>
+                                            }
+                                        })
+                                        .collect::<Vec<_>>()
+                                })
+                                .unwrap_or_default()
+                        })
+                    }}
+                </tbody>
+            </table>
+        </div>
+        <div class="w-1/2 h-40vh overflow-auto border border-gray-200 rounded bg-black text-green-300">
+            <div class="p-2 font-bold bg-gray-200 text-gray-900">"Output"</div>
+            <pre class="p-2 text-xs font-mono whitespace-pre-wrap">{output}</pre>
+        </div>
+    }
+}
+
+#[component]
+fn GasPanel(
+    gas_budget: RwSignal<Option<u64>>,
+    cost_model: RwSignal<Rc<dyn CostModel>>,
+    interpreter: RwSignal<Option<Interpreter>>,
+) -> impl IntoView {
+    let (model_error, set_model_error) = create_signal(None::<String>);
+
+    let summary = move || {
+        interpreter.with(|vm| {
+            let vm = vm.as_ref()?;
+            let gas = vm.gas()?;
+            Some(format!("{} of {} used, {} remaining", gas.used(), gas.budget, gas.remaining))
+        })
+    };
+    let out_of_gas = move || {
+        interpreter.with(|vm| {
+            let vm = vm.as_ref()?;
+            let Status::Trapped(TrapReason::OutOfGas(jump_target)) = vm.status() else {
+                return None;
+            };
+            let analysis = &vm.program().analysis;
+            let offset = analysis
+                .block(*jump_target)
+                .and_then(|block| analysis.instructions.get(block.instructions.start))
+                .map_or(0, |analyzed| analysis.code_offset + analyzed.offset);
+            Some(format!("Out of gas entering @{} at {:06X}", jump_target, offset))
+        })
+    };
+
+    view! {
+        <div class="p-2 font-bold bg-gray-200">"Gas"</div>
+        <div class="p-2 text-xs flex flex-col gap-1">
+            <label class="flex items-center gap-2">
+                "Budget"
+                <input
+                    type="text"
+                    class="w-32 px-1 font-mono border border-gray-300"
+                    placeholder="unmetered"
+                    prop:value=move || gas_budget().map(|budget| budget.to_string()).unwrap_or_default()
+                    on:change=move |event| {
+                        let text = event_target_value(&event);
+                        gas_budget.set(if text.trim().is_empty() { None } else { text.trim().parse::<u64>().ok() });
+                    }
+                />
+            </label>
+            <div class="flex items-center gap-2">
+                "Cost model: "
+                <span class="font-mono">{move || cost_model.with(|model| model.name().to_string())}</span>
+                <button
+                    class="px-2 border border-gray-300 bg-white hover:bg-gray-100"
+                    on:click=move |_| cost_model.set(Rc::new(UnitCost))
+                >
+                    "Use unit costs"
+                </button>
+            </div>
+            <div class="p-2 border-dashed border-2">
+                <FileUploadComponent
+                    accept=".ron,.toml"
+                    on_file_uploaded=move |data_option, filename| {
+                        let Some(data) = data_option else {
+                            return;
+                        };
+                        let parsed = String::from_utf8(data)
+                            .map_err(|_| "Cost table is not valid UTF-8".to_string())
+                            .and_then(|contents| CostTable::parse(&filename, &contents));
+                        match parsed {
+                            Ok(table) => {
+                                set_model_error(None);
+                                cost_model.set(Rc::new(table));
+                            }
+                            Err(message) => set_model_error(Some(message)),
+                        }
+                    }
+                />
+            </div>
+            {move || model_error().map(|message| view! { <div class="text-red-600">{message}</div> })}
+            <div class="font-mono">{summary}</div>
+            <div class="font-mono text-red-600">{out_of_gas}</div>
+        </div>
+    }
+}
+
+#[component]
+pub fn Emulator() -> impl IntoView {
+    let (filename, set_filename) = create_signal(String::new());
+    let (program, set_program) = create_signal(None::<Rc<Program>>);
+    let interpreter = create_rw_signal(None::<Interpreter>);
+    let (entry, set_entry) = create_signal(None::<u32>);
+    let (cursor, set_cursor) = create_signal(None::<usize>);
+    let (error, set_error) = create_signal(None::<String>);
+    let (content_hash, set_content_hash) = create_signal(None::<String>);
+    let breakpoints = create_rw_signal(Breakpoints::default());
+    let (break_reason, set_break_reason) = create_signal(None::<String>);
+    let host_config = create_rw_signal(HostConfig::default());
+    let gas_budget = create_rw_signal(None::<u64>);
+    let cost_model = use_cost_model();
+    let tracing = create_rw_signal(false);
+    let (heatmap, set_heatmap) = create_signal(false);
+    let history = store_value(None::<History>);
+    let (timeline_end, set_timeline_end) = create_signal(0u64);
+    let (write_target, set_write_target) = create_signal(String::new());
+
+    // Turning tracing on starts a new trace from the current instruction;
+    // turning it off keeps the recording around for export
+    create_effect(move |_| {
+        let enabled = tracing();
+        interpreter.update(|vm| {
+            if let Some(vm) = vm {
+                vm.set_tracing(enabled);
+            }
+        });
+    });
+
+    // Host behaviors apply to the running program as soon as they change;
+    // recorded history past this point no longer matches what would run
+    create_effect(move |_| {
+        let config = host_config();
+        interpreter.update(|vm| {
+            if let Some(vm) = vm {
+                vm.set_host_config(config);
+                let executed = vm.executed();
+                history.update_value(|history| {
+                    if let Some(history) = history {
+                        history.truncate(executed);
+                    }
+                });
+                set_timeline_end(executed);
+            }
+        });
+    });
+
+    // Breakpoints are remembered per file content; the hash is only set once
+    // the stored ones are in place so they aren't overwritten while loading
+    create_effect(move |_| {
+        let current = breakpoints();
+        if let Some(hash) = content_hash.get_untracked() {
+            spawn_local(async move {
+                if let Err(error) = save_state(&Breakpoints::storage_key(&hash), &current).await {
+                    log::warn!("{}", error);
+                }
+            });
+        }
+    });
+
+    let pc = create_memo(move |_| interpreter.with(|vm| vm.as_ref().map(Interpreter::pc)));
+    // Execution counts and the busiest instruction, only while the heatmap is shown
+    let coverage = create_memo(move |_| {
+        if !heatmap() {
+            return None;
+        }
+        interpreter.with(|vm| {
+            let counts = vm.as_ref()?.execution_counts().to_vec();
+            let max_count = counts.iter().copied().max().unwrap_or(0);
+            Some((counts, max_count))
+        })
+    });
+    let status = create_memo(move |_| interpreter.with(|vm| vm.as_ref().map(|vm| vm.status().clone())));
+
+    let reset = move || {
+        let (Some(program), Some(entry)) = (program.get_untracked(), entry.get_untracked()) else {
+            return;
+        };
+        let gas = gas_budget
+            .get_untracked()
+            .map(|budget| cost_model.with_untracked(|model| GasMeter::new(model.as_ref(), &program.analysis, budget)));
+        let mut vm = Interpreter::with_host_config(program, entry, host_config.get_untracked());
+        vm.set_gas_meter(gas);
+        vm.set_tracing(tracing.get_untracked());
+        history.set_value(Some(History::new(&vm)));
+        set_timeline_end(0);
+        interpreter.set(Some(vm));
+        set_break_reason(None);
+        if let Some(pc) = pc.get_untracked() {
+            scroll_into_view(&instruction_element_id(pc));
+        }
+    };
+
+    // A new budget or cost model starts the run over so that gas accounts
+    // for every block from the entry point
+    create_effect(move |_| {
+        gas_budget.track();
+        cost_model.track();
+        reset();
+    });
+
+    // Runs an action against the interpreter and follows the pc in the listing;
+    // the action reports why it stopped if a breakpoint was hit
+    let execute = move |action: &dyn Fn(&mut Interpreter) -> Option<String>| {
+        let mut reason = None;
+        interpreter.update(|vm| {
+            if let Some(vm) = vm {
+                reason = action(vm);
+                history.update_value(|history| {
+                    if let Some(history) = history {
+                        history.record(vm);
+                    }
+                });
+            }
+        });
+        set_timeline_end(history.with_value(|history| history.as_ref().map_or(0, History::end)));
+        set_break_reason(reason);
+        if let Some(pc) = pc.get_untracked() {
+            scroll_into_view(&instruction_element_id(pc));
+        }
+    };
+
+    // Between steps: snapshots the run for reverse stepping and checks
+    // breakpoints, remembering the first one that hit
+    let breakpoint_hit = move |vm: &Interpreter, reason: &mut Option<String>| {
+        history.update_value(|history| {
+            if let Some(history) = history {
+                history.record(vm);
+            }
+        });
+        *reason = breakpoints.with_untracked(|breakpoints| breakpoints.hit(vm));
+        reason.is_some()
+    };
+
+    // Moves to the state after `step` instructions of the recorded run
+    let travel_to = move |step: u64| {
+        interpreter.update(|vm| {
+            if let Some(vm) = vm {
+                if let Some(restored) = history.with_value(|history| history.as_ref()?.seek(vm, step)) {
+                    *vm = restored;
+                }
+            }
+        });
+        set_break_reason(None);
+        if let Some(pc) = pc.get_untracked() {
+            scroll_into_view(&instruction_element_id(pc));
+        }
+    };
+    let executed = create_memo(move |_| interpreter.with(|vm| vm.as_ref().map_or(0, Interpreter::executed)));
+
+    let run_back_to_write = move |_| {
+        let text = write_target.get_untracked();
+        let Some(target) = WriteTarget::parse(&text) else {
+            set_break_reason(Some(format!("'{}' is neither a register nor an address", text)));
+            return;
+        };
+        let found = interpreter.with_untracked(|vm| {
+            let vm = vm.as_ref()?;
+            history.with_value(|history| history.as_ref()?.find_previous_write(vm, target))
+        });
+        match found {
+            Some(step) => {
+                // Stop just before the writing instruction so it is the one highlighted
+                travel_to(step - 1);
+                set_break_reason(Some(format!("write to {} by step {}", text.trim(), step)));
+            }
+            None => set_break_reason(Some(format!("no earlier write to {}", text.trim()))),
+        }
+    };
+
+    let on_file_uploaded = move |data_option: Option<Vec<u8>>, name: String| {
+        let Some(data) = data_option else {
+            return;
+        };
+        match Program::parse(&data) {
+            Ok(parsed) => {
+                let hash = storage::content_hash(&data);
+                set_content_hash(None);
+                breakpoints.set(Breakpoints::default());
+                spawn_local(async move {
+                    match load_state(&Breakpoints::storage_key(&hash)).await {
+                        Ok(stored) => breakpoints.set(stored.unwrap_or_default()),
+                        Err(error) => log::warn!("{}", error),
+                    }
+                    set_content_hash(Some(hash));
+                });
+                let first_export = parsed.exports.first().map(|(_, jump_target)| *jump_target);
+                set_filename(name);
+                set_error(None);
+                set_cursor(None);
+                set_program(Some(Rc::new(parsed)));
+                set_entry(first_export);
+                reset();
+            }
+            Err(message) => set_error(Some(message.to_string())),
+        }
+    };
+
+    let unload = move |_| {
+        set_program(None);
+        set_filename(String::new());
+        set_entry(None);
+        set_cursor(None);
+        set_content_hash(None);
+        set_break_reason(None);
+        breakpoints.set(Breakpoints::default());
+        interpreter.set(None);
+    };
+
+    let button_class = "px-3 py-1 text-sm font-semibold text-gray-700 bg-white hover:bg-gray-100 rounded-xs border border-gray-300 shadow-sm cursor-pointer disabled:opacity-50";
+    let can_run = move || status().as_ref() == Some(&Status::Running);
+
+    view! {
+        <div class="flex flex-col">
+            <Show
+                when=move || program().is_some()
+                fallback=move || {
+                    view! {
+                        <div class="h-60vh p-4">
+                            <div class="border-dashed border-4 w-full h-full p-4">
+                                <FileUploadComponent on_file_uploaded=on_file_uploaded/>
+                                {move || error().map(|message| view! { <div class="text-red-600 mt-2">{message}</div> })}
+                            </div>
+                        </div>
+                    }
+                }
+            >
+                <div class="flex flex-wrap h-16 w-full items-center gap-2 px-4 md:px-6 bg-gray-100">
+                    <span class="flex items-center text-sm"><div class="i-mdi-file" />{filename}</span>
+                    <select
+                        class="h-8 px-2 text-sm rounded-sm border border-gray-300 bg-white"
+                        on:change=move |event| {
+                            set_entry(event_target_value(&event).parse::<u32>().ok());
+                            reset();
+                        }
+                    >
+                        {move || {
+                            program()
+                                .map(|program| {
+                                    program
+                                        .exports
+                                        .iter()
+                                        .map(|(name, jump_target)| {
+                                            let jump_target = *jump_target;
+                                            view! {
+                                                <option value=jump_target.to_string() selected=move || entry() == Some(jump_target)>
+                                                    {format!("{} @{}", name, jump_target)}
+                                                </option>
+                                            }
+                                        })
+                                        .collect::<Vec<_>>()
+                                })
+                                .unwrap_or_default()
+                        }}
+                    </select>
+                    <button class=button_class on:click=move |_| reset()>"Reset"</button>
+                    <button class=button_class disabled=move || !can_run() on:click=move |_| execute(&|vm: &mut Interpreter| {
+                        vm.step();
+                        None
+                    })>"Step"</button>
+                    <button class=button_class disabled=move || !can_run() on:click=move |_| execute(&|vm: &mut Interpreter| {
+                        let mut reason = None;
+                        vm.step_over_until(|vm| breakpoint_hit(vm, &mut reason));
+                        reason
+                    })>"Step over"</button>
+                    <button
+                        class=button_class
+                        disabled=move || !can_run() || cursor().is_none()
+                        on:click=move |_| {
+                            if let Some(target) = cursor.get_untracked() {
+                                execute(&move |vm: &mut Interpreter| {
+                                    let mut reason = None;
+                                    vm.run_until(|vm| vm.pc() == target || breakpoint_hit(vm, &mut reason));
+                                    reason
+                                });
+                            }
+                        }
+                    >"Run to cursor"</button>
+                    <button class=button_class disabled=move || !can_run() on:click=move |_| execute(&|vm: &mut Interpreter| {
+                        let mut reason = None;
+                        vm.run_until(|vm| breakpoint_hit(vm, &mut reason));
+                        reason
+                    })>"Run"</button>
+                    <button class=button_class disabled=move || executed() == 0 on:click=move |_| travel_to(executed.get_untracked() - 1)>
+                        "Step back"
+                    </button>
+                    <input
+                        type="text"
+                        class="w-24 h-8 px-1 text-sm font-mono border border-gray-300"
+                        placeholder="a0 or 0x…"
+                        title="Register or address"
+                        prop:value=write_target
+                        on:input=move |event| set_write_target(event_target_value(&event))
+                    />
+                    <button class=button_class disabled=move || executed() == 0 on:click=run_back_to_write>"Back to write"</button>
+                    <button class=button_class on:click=unload>"Unload"</button>
+                    <label class="flex items-center gap-1 text-sm">
+                        <input type="checkbox" prop:checked=heatmap on:change=move |event| set_heatmap(event_target_checked(&event))/>
+                        "Heatmap"
+                    </label>
+                    <span class="ml-4 text-sm font-mono">
+                        {move || {
+                            interpreter.with(|vm| {
+                                vm.as_ref().map(|vm| format!("{} after {} instructions", vm.status(), vm.executed()))
+                            })
+                        }}
+                        {move || break_reason().map(|reason| format!(", stopped at {}", reason))}
+                    </span>
+                </div>
+                <div class="flex items-center w-full gap-2 px-4 pt-2 text-sm font-mono">
+                    <span>"Timeline"</span>
+                    <input
+                        type="range"
+                        class="flex-1"
+                        min="0"
+                        max=move || timeline_end().to_string()
+                        prop:value=move || executed().to_string()
+                        on:change=move |event| {
+                            if let Ok(step) = event_target_value(&event).parse::<u64>() {
+                                travel_to(step);
+                            }
+                        }
+                    />
+                    <span>{move || format!("{} / {}", executed(), timeline_end())}</span>
+                </div>
+                <div class="flex flex-row w-full p-4 gap-4">
+                    <div class="w-2/3 h-70vh overflow-auto border border-gray-200 rounded">
+                        {move || {
+                            program()
+                                .map(|program| {
+                                    let analysis = &program.analysis;
+                                    analysis
+                                        .instructions
+                                        .iter()
+                                        .enumerate()
+                                        .map(|(index, analyzed)| {
+                                            let started_block = analysis
+                                                .block_of_instruction(index)
+                                                .filter(|block| block.instructions.start == index);
+                                            let is_block_start = started_block.is_some();
+                                            let gas_cost = started_block.map(|block| {
+                                                cost_model.with(|model| format!("{} gas", block_cost(model.as_ref(), analysis, block)))
+                                            });
+                                            view! {
+                                                <div
+                                                    id=instruction_element_id(index)
+                                                    class=move || {
+                                                        let heat = coverage.with(|coverage| {
+                                                            coverage.as_ref().filter(|_| pc() != Some(index)).map(|(counts, max_count)| {
+                                                                heat_class(heat_level(counts.get(index).copied().unwrap_or(0), *max_count))
+                                                            })
+                                                        });
+                                                        format!("flex font-mono text-sm cursor-pointer hover:bg-gray-100 {}", heat.unwrap_or_default())
+                                                    }
+                                                    class:border-t=is_block_start
+                                                    class:bg-yellow-200=move || pc() == Some(index)
+                                                    class:ring-1=move || cursor() == Some(index)
+                                                    on:click=move |_| set_cursor(Some(index))
+                                                >
+                                                    <div
+                                                        class="w-6 p-1 flex items-center justify-center hover:bg-red-100"
+                                                        title="Toggle breakpoint"
+                                                        on:click=move |event| {
+                                                            event.stop_propagation();
+                                                            breakpoints.update(|breakpoints| breakpoints.toggle(index));
+                                                        }
+                                                    >
+                                                        {move || {
+                                                            breakpoints.with(|breakpoints| {
+                                                                breakpoints
+                                                                    .breakpoints
+                                                                    .iter()
+                                                                    .find(|breakpoint| breakpoint.instruction == index)
+                                                                    .map(|breakpoint| {
+                                                                        let color = if breakpoint.enabled { "bg-red-600" } else { "bg-gray-400" };
+                                                                        view! { <div class=format!("w-2 h-2 rounded-full {}", color) /> }
+                                                                    })
+                                                            })
+                                                        }}
+                                                    </div>
+                                                    <div class="w-24 p-1 text-gray-500">
+                                                        {format!("{:06X}", analysis.code_offset + analyzed.offset)}
+                                                    </div>
+                                                    <div class="flex-1 p-1">{analyzed.instruction.to_string()}</div>
+                                                    <div class="w-20 p-1 text-xs text-right text-gray-500">{gas_cost}</div>
+                                                    <Show when=move || heatmap()>
+                                                        <div class="w-16 p-1 text-xs text-right" title="Times executed">
+                                                            {move || {
+                                                                coverage.with(|coverage| {
+                                                                    coverage
+                                                                        .as_ref()
+                                                                        .and_then(|(counts, _)| counts.get(index))
+                                                                        .map_or_else(String::new, u64::to_string)
+                                                                })
+                                                            }}
+                                                        </div>
+                                                    </Show>
+                                                </div>
+                                            }
+                                        })
+                                        .collect::<Vec<_>>()
+                                })
+                                .unwrap_or_default()
+                        }}
+                    </div>
+                    <div class="w-1/3 h-70vh overflow-auto border border-gray-200 rounded bg-gray-50">
+                        <div class="p-2 font-bold bg-gray-200">"Registers"</div>
+                        <Registers interpreter=interpreter/>
+                        <Show when=move || heatmap()>
+                            <HotSpotPanel
+                                interpreter=interpreter
+                                on_selected=move |index| {
+                                    set_cursor(Some(index));
+                                    scroll_into_view(&instruction_element_id(index));
+                                }
+                            />
+                        </Show>
+                        <BreakpointPanel breakpoints=breakpoints program=program/>
+                        <GasPanel gas_budget=gas_budget cost_model=cost_model interpreter=interpreter/>
+                        <HostPanel host_config=host_config program=program/>
+                    </div>
+                </div>
+                <div class="flex flex-row w-full px-4 pb-4 gap-4">
+                    <HostCallLog interpreter=interpreter/>
+                </div>
+                <MemoryInspector interpreter=interpreter/>
+                <TraceView
+                    interpreter=interpreter
+                    tracing=tracing
+                    filename=filename
+                    on_entry_selected=move |index| {
+                        set_cursor(Some(index));
+                        scroll_into_view(&instruction_element_id(index));
+                    }
+                />
+            </Show>
+        </div>
+    }
+}
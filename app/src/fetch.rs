@@ -0,0 +1,37 @@
+use gloo_net::http::Request;
+use polkavm_common::program::BLOB_MAGIC;
+
+/// Downloads a blob, refusing anything that isn't a PolkaVM program so that
+/// e.g. an HTML error page isn't handed to the disassembler.
+pub async fn fetch_blob(url: &str) -> Result<Vec<u8>, String> {
+    let response = Request::get(url)
+        .send()
+        .await
+        .map_err(|error| format!("Failed to fetch {}: {}", url, error))?;
+    if !response.ok() {
+        return Err(format!(
+            "Failed to fetch {}: HTTP {} {}",
+            url,
+            response.status(),
+            response.status_text()
+        ));
+    }
+
+    let data = response
+        .binary()
+        .await
+        .map_err(|error| format!("Failed to read {}: {}", url, error))?;
+    if !data.starts_with(&BLOB_MAGIC) {
+        return Err(format!("{} is not a PolkaVM blob", url));
+    }
+    Ok(data)
+}
+
+/// Last path segment of `url`, to show as the file name.
+pub fn url_filename(url: &str) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    match path.rsplit('/').next() {
+        Some(name) if !name.is_empty() => name.to_string(),
+        _ => "download.polkavm".to_string(),
+    }
+}
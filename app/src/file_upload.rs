@@ -1,10 +1,15 @@
 use leptos::*;
 use std::rc::Rc;
 use wasm_bindgen::{closure::Closure, JsCast};
-use web_sys::{File, FileReader, HtmlInputElement, DragEvent, ProgressEvent};
+use web_sys::{Blob, BlobPropertyBag, File, FileReader, HtmlAnchorElement, HtmlInputElement, DragEvent, ProgressEvent, Url};
 
 #[component]
-pub fn FileUploadComponent<F: Fn(Option<Vec<u8>>, String) + 'static>(on_file_uploaded: F) -> impl IntoView {
+pub fn FileUploadComponent<F: Fn(Option<Vec<u8>>, String) + 'static>(
+    on_file_uploaded: F,
+    #[prop(default = ".polkavm")] accept: &'static str,
+    /// Accept several files at once, reporting each one separately
+    #[prop(optional)] multiple: bool,
+) -> impl IntoView {
     let on_file_uploaded = Rc::new(on_file_uploaded);
 
     let process_file = |on_file_uploaded: Rc<F>, file: File| {
@@ -34,7 +39,8 @@ pub fn FileUploadComponent<F: Fn(Option<Vec<u8>>, String) + 'static>(on_file_upl
         move |event: web_sys::Event| {
             let input: HtmlInputElement = event.target().unwrap().dyn_into().unwrap();
             let files = input.files().unwrap();
-            if let Some(file) = files.get(0) {
+            let count = if multiple { files.length() } else { 1 };
+            for file in (0..count).filter_map(|index| files.get(index)) {
                 process_file(on_file_uploaded_cloned.clone(), file);
             }
         }
@@ -47,7 +53,8 @@ pub fn FileUploadComponent<F: Fn(Option<Vec<u8>>, String) + 'static>(on_file_upl
             event.stop_propagation();
             if let Some(data_transfer) = event.data_transfer() {
                 if let Some(files) = data_transfer.files() {
-                    if let Some(file) = files.get(0) {
+                    let count = if multiple { files.length() } else { 1 };
+                    for file in (0..count).filter_map(|index| files.get(index)) {
                         process_file(on_file_uploaded_cloned.clone(), file);
                     }
                 }
@@ -64,8 +71,34 @@ pub fn FileUploadComponent<F: Fn(Option<Vec<u8>>, String) + 'static>(on_file_upl
             }
         >
 
-            "Drag and drop your .polkavm file here or click to upload"
-            <input type="file" accept=".polkavm" on:change=on_upload/>
+            {format!("Drag and drop your {} {} here or click to upload", accept, if multiple { "files" } else { "file" })}
+            <input type="file" accept=accept multiple=multiple on:change=on_upload/>
         </div>
     }
 }
+
+/// Offers `contents` to the user as a file download.
+pub fn download_file(filename: &str, mime_type: &str, contents: &str) {
+    let parts = js_sys::Array::of1(&contents.into());
+    let mut options = BlobPropertyBag::new();
+    options.type_(mime_type);
+    let Ok(blob) = Blob::new_with_str_sequence_and_options(&parts, &options) else {
+        log::warn!("Failed to create {}", filename);
+        return;
+    };
+    let Ok(url) = Url::create_object_url_with_blob(&blob) else {
+        log::warn!("Failed to create a download URL for {}", filename);
+        return;
+    };
+
+    let anchor = document()
+        .create_element("a")
+        .ok()
+        .and_then(|element| element.dyn_into::<HtmlAnchorElement>().ok());
+    if let Some(anchor) = anchor {
+        anchor.set_href(&url);
+        anchor.set_download(filename);
+        anchor.click();
+    }
+    let _ = Url::revoke_object_url(&url);
+}
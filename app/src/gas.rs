@@ -0,0 +1,167 @@
+use crate::analysis::{opcode_name, BasicBlock, ProgramAnalysis};
+use leptos::*;
+use polkavm_common::program::Instruction;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::rc::Rc;
+
+/// Prices instructions. Gas is usually charged per basic block, so a cost
+/// model only has to say what each instruction contributes to its block.
+pub trait CostModel: fmt::Debug {
+    fn name(&self) -> &str;
+    fn instruction_cost(&self, instruction: &Instruction) -> u64;
+}
+
+/// Flat cost of one gas per instruction.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct UnitCost;
+
+impl CostModel for UnitCost {
+    fn name(&self) -> &str {
+        "unit"
+    }
+
+    fn instruction_cost(&self, _instruction: &Instruction) -> u64 {
+        1
+    }
+}
+
+/// Per-opcode costs, loadable from RON or TOML so that different runtime
+/// versions can be matched without a rebuild, e.g.
+///
+/// ```ron
+/// CostTable(name: "v0.9", default: 1, opcodes: { "div_unsigned": 4, "ecalli": 10 })
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CostTable {
+    pub name: String,
+    #[serde(default = "default_instruction_cost")]
+    pub default: u64,
+    /// Keyed by `opcode_name`
+    #[serde(default)]
+    pub opcodes: BTreeMap<String, u64>,
+}
+
+fn default_instruction_cost() -> u64 {
+    1
+}
+
+impl CostTable {
+    pub fn parse(filename: &str, contents: &str) -> Result<Self, String> {
+        if filename.ends_with(".toml") {
+            toml::from_str(contents).map_err(|error| format!("Invalid cost table: {}", error))
+        } else {
+            ron::from_str(contents).map_err(|error| format!("Invalid cost table: {}", error))
+        }
+    }
+}
+
+impl CostModel for CostTable {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn instruction_cost(&self, instruction: &Instruction) -> u64 {
+        self.opcodes.get(&opcode_name(instruction)).copied().unwrap_or(self.default)
+    }
+}
+
+/// Makes the cost model chosen in the emulator available to every page, so
+/// the disassembly listing prices blocks the same way.
+pub fn provide_cost_model() {
+    provide_context(create_rw_signal::<Rc<dyn CostModel>>(Rc::new(UnitCost)));
+}
+
+pub fn use_cost_model() -> RwSignal<Rc<dyn CostModel>> {
+    expect_context()
+}
+
+pub fn block_cost(model: &dyn CostModel, analysis: &ProgramAnalysis, block: &BasicBlock) -> u64 {
+    analysis
+        .instructions
+        .get(block.instructions.clone())
+        .unwrap_or_default()
+        .iter()
+        .map(|analyzed| model.instruction_cost(&analyzed.instruction))
+        .sum()
+}
+
+/// Static cost of every block, keyed by jump target.
+pub fn block_costs(model: &dyn CostModel, analysis: &ProgramAnalysis) -> HashMap<u32, u64> {
+    analysis
+        .blocks
+        .iter()
+        .map(|block| (block.jump_target, block_cost(model, analysis, block)))
+        .collect()
+}
+
+/// When a `GasMeter` takes gas from the budget.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Charging {
+    /// The whole cost of a block on entry, so running out of gas always
+    /// traps at a block boundary
+    PerBlock,
+    /// Each instruction right before it executes, the way the JAM PVM does
+    PerInstruction,
+}
+
+/// Gas accounting for one emulated run.
+#[derive(Clone, Debug)]
+pub struct GasMeter {
+    pub model_name: String,
+    pub charging: Charging,
+    pub budget: u64,
+    pub remaining: u64,
+    /// Keyed by jump target or by instruction index, depending on `charging`
+    costs: Rc<HashMap<u32, u64>>,
+}
+
+impl GasMeter {
+    pub fn new(model: &dyn CostModel, analysis: &ProgramAnalysis, budget: u64) -> Self {
+        GasMeter {
+            model_name: model.name().to_string(),
+            charging: Charging::PerBlock,
+            budget,
+            remaining: budget,
+            costs: Rc::new(block_costs(model, analysis)),
+        }
+    }
+
+    pub fn per_instruction(model: &dyn CostModel, analysis: &ProgramAnalysis, budget: u64) -> Self {
+        let costs = analysis
+            .instructions
+            .iter()
+            .enumerate()
+            .map(|(index, analyzed)| (index as u32, model.instruction_cost(&analyzed.instruction)))
+            .collect();
+        GasMeter {
+            model_name: model.name().to_string(),
+            charging: Charging::PerInstruction,
+            budget,
+            remaining: budget,
+            costs: Rc::new(costs),
+        }
+    }
+
+    pub fn used(&self) -> u64 {
+        self.budget - self.remaining
+    }
+
+    pub fn refill(&mut self) {
+        self.remaining = self.budget;
+    }
+
+    /// Charges for entering a block, or for executing an instruction when
+    /// charging per instruction; `false` if the budget can't cover it.
+    pub fn charge(&mut self, key: u32) -> bool {
+        let cost = self.costs.get(&key).copied().unwrap_or(0);
+        match self.remaining.checked_sub(cost) {
+            Some(remaining) => {
+                self.remaining = remaining;
+                true
+            }
+            None => false,
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use crate::breakpoints::parse_number;
+use crate::interpreter::Interpreter;
+use polkavm_common::program::Reg;
+
+// Snapshot spacing starts fine-grained and doubles whenever the snapshot
+// limit is reached, so memory stays bounded however long the run
+const INITIAL_SNAPSHOT_INTERVAL: u64 = 1_000;
+const MAX_SNAPSHOTS: usize = 64;
+
+/// What "run back to the previous write" looks for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WriteTarget {
+    /// A step that changed the register's value
+    Register(Reg),
+    /// A store overlapping the byte at this address
+    Memory(u32),
+}
+
+impl WriteTarget {
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        if let Some(&reg) = Reg::ALL.iter().find(|reg| reg.to_string() == text) {
+            return Some(WriteTarget::Register(reg));
+        }
+        parse_number(text).map(WriteTarget::Memory)
+    }
+
+    fn written(&self, regs_before: &[u32], after: &Interpreter) -> bool {
+        match *self {
+            WriteTarget::Register(reg) => regs_before[reg as usize] != after.reg(reg),
+            WriteTarget::Memory(address) => after
+                .last_accesses()
+                .iter()
+                .any(|access| access.write && access.overlaps(&(address..address.saturating_add(1)))),
+        }
+    }
+}
+
+/// Periodic snapshots of one run. Any earlier point is reached by restoring
+/// the closest snapshot before it and replaying, which is exact because
+/// emulated execution is deterministic.
+#[derive(Clone, Debug)]
+pub struct History {
+    /// Ordered by executed instruction count, starting with the initial state
+    snapshots: Vec<Interpreter>,
+    interval: u64,
+    /// Furthest point the run has reached
+    end: u64,
+}
+
+impl History {
+    pub fn new(initial: &Interpreter) -> Self {
+        History {
+            snapshots: vec![initial.snapshot()],
+            interval: INITIAL_SNAPSHOT_INTERVAL,
+            end: initial.executed(),
+        }
+    }
+
+    pub fn end(&self) -> u64 {
+        self.end
+    }
+
+    /// Called after every step; takes a snapshot when one is due.
+    pub fn record(&mut self, interpreter: &Interpreter) {
+        let executed = interpreter.executed();
+        self.end = self.end.max(executed);
+        let last = self.snapshots.last().map_or(0, Interpreter::executed);
+        if executed < last + self.interval {
+            return;
+        }
+
+        self.snapshots.push(interpreter.snapshot());
+        if self.snapshots.len() > MAX_SNAPSHOTS {
+            let mut index = 0;
+            self.snapshots.retain(|_| {
+                index += 1;
+                index % 2 == 1
+            });
+            self.interval *= 2;
+        }
+    }
+
+    /// Forgets everything after `executed`, for when the run has diverged
+    /// from what was recorded.
+    pub fn truncate(&mut self, executed: u64) {
+        self.snapshots.retain(|snapshot| snapshot.executed() <= executed);
+        self.end = executed;
+    }
+
+    /// State after `target` instructions, carrying over `current`'s trace
+    /// rewound to that point.
+    pub fn seek(&self, current: &Interpreter, target: u64) -> Option<Interpreter> {
+        let target = target.min(self.end);
+        let snapshot = self.snapshots.iter().rev().find(|snapshot| snapshot.executed() <= target)?;
+        let mut interpreter = snapshot.snapshot();
+        while interpreter.executed() < target && interpreter.is_running() {
+            interpreter.step();
+        }
+        interpreter.set_tracing(current.is_tracing());
+        interpreter.set_trace(current.trace().map(|trace| trace.rewound_to(target)));
+        Some(interpreter)
+    }
+
+    /// Instruction count right after the most recent step, at or before
+    /// `current`, that wrote to `target`.
+    pub fn find_previous_write(&self, current: &Interpreter, target: WriteTarget) -> Option<u64> {
+        let end = current.executed();
+        for (index, snapshot) in self.snapshots.iter().enumerate().rev() {
+            if snapshot.executed() >= end {
+                continue;
+            }
+            let segment_end = self.snapshots.get(index + 1).map_or(end, |next| next.executed().min(end));
+
+            let mut interpreter = snapshot.snapshot();
+            let mut found = None;
+            while interpreter.executed() < segment_end && interpreter.is_running() {
+                let regs_before = *interpreter.regs();
+                interpreter.step();
+                if target.written(&regs_before, &interpreter) {
+                    found = Some(interpreter.executed());
+                }
+            }
+            if found.is_some() {
+                return found;
+            }
+        }
+        None
+    }
+}
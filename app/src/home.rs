@@ -1,10 +1,12 @@
 use leptos::*;
+use crate::recent_files::RecentFiles;
+use crate::samples::SampleGallery;
 
 #[component]
 pub fn Home() -> impl IntoView {
     view! {
         <div class="container p-8 mx-auto">
-            <h1 class="text-2xl font-bold text-center md:text-4xl lg:text-5xl">"polka.run"</h1>
+            <h1 class="text-2xl font-bold text-center md:text-4xl lg:text-5xl dark:text-#E1FF66">"polka.run"</h1>
             <div class="flex flex-col lg:flex-row gap-6 mt-6">
                 <div class="w-full lg:w-2/4 p-4">
                     <p class="text-md leading-relaxed mt-4">
@@ -13,6 +15,8 @@ pub fn Home() -> impl IntoView {
                         "Rust/C/asm guest programs into RISC-V based bytecode. We have built graphical "
                         "interface for disassembler to improve accessibility to understand binaries PolkaVM produces. "
                     </p>
+                    <SampleGallery/>
+                    <RecentFiles/>
                 </div>
                 <div class="w-full lg:w-2/4">
                     <pre class="text-xs xl:text-sm">
@@ -0,0 +1,169 @@
+use crate::interpreter::{Memory, TrapReason};
+use polkavm_common::program::Reg;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const ARGUMENT_REGS: [Reg; 6] = [Reg::A0, Reg::A1, Reg::A2, Reg::A3, Reg::A4, Reg::A5];
+
+/// What an emulated host function does when the guest calls it.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum HostBehavior {
+    /// Prints the `a1` bytes at address `a0` to the output console
+    Log,
+    /// Returns a constant in `a0`
+    Return(u32),
+    #[default]
+    Trap,
+}
+
+impl HostBehavior {
+    pub const KINDS: [&'static str; 3] = ["trap", "log", "return"];
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            HostBehavior::Log => "log",
+            HostBehavior::Return(_) => "return",
+            HostBehavior::Trap => "trap",
+        }
+    }
+
+    /// The `Return` constant, or 0 for the other behaviors
+    pub fn constant(&self) -> u32 {
+        match self {
+            HostBehavior::Return(value) => *value,
+            _ => 0,
+        }
+    }
+
+    /// Switches to `kind`, keeping the current constant when it is `return`.
+    pub fn with_kind(&self, kind: &str) -> HostBehavior {
+        match kind {
+            "log" => HostBehavior::Log,
+            "return" => HostBehavior::Return(self.constant()),
+            _ => HostBehavior::Trap,
+        }
+    }
+}
+
+/// Per-import behaviors, loadable from RON or TOML, e.g.
+///
+/// ```ron
+/// HostConfig(default: Trap, imports: { "print": Log, "get_number": Return(42) })
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct HostConfig {
+    #[serde(default)]
+    pub default: HostBehavior,
+    #[serde(default)]
+    pub imports: BTreeMap<String, HostBehavior>,
+}
+
+impl HostConfig {
+    pub fn parse(filename: &str, contents: &str) -> Result<Self, String> {
+        if filename.ends_with(".toml") {
+            toml::from_str(contents).map_err(|error| format!("Invalid host config: {}", error))
+        } else {
+            ron::from_str(contents).map_err(|error| format!("Invalid host config: {}", error))
+        }
+    }
+
+    pub fn behavior(&self, import: &str) -> &HostBehavior {
+        self.imports.get(import).unwrap_or(&self.default)
+    }
+
+    pub fn set_behavior(&mut self, import: &str, behavior: HostBehavior) {
+        self.imports.insert(import.to_string(), behavior);
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HostCallRecord {
+    pub index: u32,
+    pub name: String,
+    pub args: [u32; ARGUMENT_REGS.len()],
+    /// Value returned in `a0`, if the call returned
+    pub result: Option<u32>,
+}
+
+/// Host side of emulated execution: import behaviors, console output and a
+/// record of every call.
+#[derive(Clone, Debug, Default)]
+pub struct HostEnvironment {
+    pub config: HostConfig,
+    pub imports: Vec<String>,
+    pub output: String,
+    pub calls: Vec<HostCallRecord>,
+}
+
+impl HostEnvironment {
+    pub fn new(config: HostConfig, imports: Vec<String>) -> Self {
+        HostEnvironment {
+            config,
+            imports,
+            output: String::new(),
+            calls: Vec::new(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.output.clear();
+        self.calls.clear();
+    }
+
+    /// Handles `ecalli index`, updating registers in place.
+    pub fn call(&mut self, index: u32, regs: &mut [u32], memory: &mut Memory) -> Result<(), TrapReason> {
+        let name = self
+            .imports
+            .get(index as usize)
+            .cloned()
+            .unwrap_or_else(|| format!("#{}", index));
+        let mut args = [0; ARGUMENT_REGS.len()];
+        for (arg, reg) in args.iter_mut().zip(ARGUMENT_REGS) {
+            *arg = regs[reg as usize];
+        }
+        let mut record = HostCallRecord {
+            index,
+            name,
+            args,
+            result: None,
+        };
+
+        let outcome = match self.config.behavior(&record.name).clone() {
+            HostBehavior::Log => memory.read(args[0], args[1]).map(|bytes| {
+                self.output.push_str(&String::from_utf8_lossy(&bytes));
+                if !self.output.ends_with('\n') {
+                    self.output.push('\n');
+                }
+                record.result = Some(regs[Reg::A0 as usize]);
+            }),
+            HostBehavior::Return(value) => {
+                regs[Reg::A0 as usize] = value;
+                record.result = Some(value);
+                Ok(())
+            }
+            HostBehavior::Trap => Err(TrapReason::UnhandledHostCall(index)),
+        };
+
+        self.calls.push(record);
+        outcome
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn switching_to_return_then_editing_the_value_updates_the_config() {
+        let mut config = HostConfig::default();
+        let behavior = config.behavior("get_number").with_kind("return");
+        config.set_behavior("get_number", behavior);
+        assert_eq!(config.behavior("get_number"), &HostBehavior::Return(0));
+        assert_eq!(config.behavior("get_number").kind(), "return");
+
+        config.set_behavior("get_number", HostBehavior::Return(42));
+        assert_eq!(config.behavior("get_number"), &HostBehavior::Return(42));
+        assert_eq!(config.behavior("get_number").constant(), 42);
+        assert_eq!(config.behavior("other"), &HostBehavior::Trap);
+    }
+}
@@ -0,0 +1,1072 @@
+use crate::analysis::{analyze_program, control_flow, opcode_name, ControlFlow, ProgramAnalysis};
+use crate::gas::{Charging, GasMeter};
+use crate::host::{HostConfig, HostEnvironment};
+use crate::trace::{RegisterWrite, Trace, TraceAccess, TraceEntry};
+use polkavm_common::abi::{MemoryMap, VM_ADDR_RETURN_TO_HOST, VM_CODE_ADDRESS_ALIGNMENT, VM_MAX_PAGE_SIZE};
+use polkavm_common::program::{Instruction, ProgramBlob, Reg};
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Range;
+use std::rc::Rc;
+
+pub const REG_COUNT: usize = Reg::ALL.len();
+
+// Upper bound on instructions executed by a single run request so a guest
+// stuck in a loop can't freeze the page
+pub const MAX_STEPS_PER_RUN: u64 = 10_000_000;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TrapReason {
+    /// The guest executed a `trap` instruction
+    Trap,
+    InvalidJump(u32),
+    MemoryAccess { address: u32, write: bool },
+    UnhandledHostCall(u32),
+    /// Execution ran off the end of the code
+    InvalidInstructionPointer,
+    Unimplemented(String),
+    /// Not enough gas left to enter the block at this jump target, or to
+    /// execute an instruction of it
+    OutOfGas(u32),
+}
+
+impl fmt::Display for TrapReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TrapReason::Trap => write!(f, "trap instruction"),
+            TrapReason::InvalidJump(address) => write!(f, "invalid jump to 0x{:08x}", address),
+            TrapReason::MemoryAccess { address, write: true } => write!(f, "invalid write to 0x{:08x}", address),
+            TrapReason::MemoryAccess { address, write: false } => write!(f, "invalid read from 0x{:08x}", address),
+            TrapReason::UnhandledHostCall(index) => write!(f, "unhandled host call #{}", index),
+            TrapReason::InvalidInstructionPointer => write!(f, "execution ran past the end of the code"),
+            TrapReason::Unimplemented(instruction) => write!(f, "unimplemented instruction: {}", instruction),
+            TrapReason::OutOfGas(jump_target) => write!(f, "out of gas in @{}", jump_target),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Status {
+    Running,
+    /// Returned to the host from the entry point
+    Halted,
+    Trapped(TrapReason),
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Status::Running => write!(f, "running"),
+            Status::Halted => write!(f, "halted"),
+            Status::Trapped(reason) => write!(f, "trapped: {}", reason),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MemoryAccess {
+    pub address: u32,
+    pub length: u32,
+    pub write: bool,
+}
+
+impl MemoryAccess {
+    pub fn overlaps(&self, range: &Range<u32>) -> bool {
+        self.address < range.end && range.start < self.address.saturating_add(self.length)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MemoryRegion {
+    pub name: &'static str,
+    pub address: u32,
+    pub data: Vec<u8>,
+    pub writable: bool,
+}
+
+impl MemoryRegion {
+    pub fn range(&self) -> Range<u32> {
+        self.address..self.address + self.data.len() as u32
+    }
+}
+
+/// How the guest may access a span of its address space.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PageAccess {
+    ReadOnly,
+    ReadWrite,
+    Inaccessible,
+}
+
+/// Contiguous span of the address space with uniform access, including the
+/// unmapped gaps between regions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MemorySegment {
+    pub name: &'static str,
+    pub range: Range<u32>,
+    pub access: PageAccess,
+}
+
+#[derive(Clone, Debug)]
+pub struct Memory {
+    pub map: MemoryMap,
+    pub regions: Vec<MemoryRegion>,
+    /// Current top of the heap, moved by `sbrk`
+    pub heap_top: u32,
+}
+
+impl Memory {
+    fn new(map: MemoryMap, ro_data: &[u8], rw_data: &[u8]) -> Self {
+        let mut ro = ro_data.to_vec();
+        ro.resize(map.ro_data_range().len(), 0);
+        // rw_data is followed by zeroed bss up to the configured size
+        let mut rw = rw_data.to_vec();
+        rw.resize(map.rw_data_range().len(), 0);
+
+        let regions = vec![
+            MemoryRegion {
+                name: "ro_data",
+                address: map.ro_data_address(),
+                data: ro,
+                writable: false,
+            },
+            MemoryRegion {
+                name: "rw_data",
+                address: map.rw_data_address(),
+                data: rw,
+                writable: true,
+            },
+            // The heap begins inside the last rw_data page, so its own pages
+            // only start once that page is used up
+            MemoryRegion {
+                name: "heap",
+                address: map.rw_data_range().end,
+                data: Vec::new(),
+                writable: true,
+            },
+            MemoryRegion {
+                name: "stack",
+                address: map.stack_address_low(),
+                data: vec![0; map.stack_range().len()],
+                writable: true,
+            },
+        ];
+
+        Memory {
+            heap_top: map.heap_base(),
+            map,
+            regions,
+        }
+    }
+
+    /// Memory made of the given regions instead of the blob's layout.
+    pub fn from_regions(map: MemoryMap, regions: Vec<MemoryRegion>) -> Self {
+        Memory {
+            heap_top: map.heap_base(),
+            map,
+            regions,
+        }
+    }
+
+    /// The slices of the regions an access covers, in address order; it may
+    /// span adjacent regions but must not touch unmapped memory.
+    fn spans_mut(&mut self, address: u32, length: u32, write: bool) -> Result<Vec<&mut [u8]>, TrapReason> {
+        let error = TrapReason::MemoryAccess { address, write };
+        let end = address.checked_add(length).ok_or(error.clone())?;
+        if length == 0 {
+            let mapped = self
+                .regions
+                .iter()
+                .any(|region| region.address <= address && address <= region.range().end);
+            return if mapped { Ok(Vec::new()) } else { Err(error) };
+        }
+
+        let mut regions = self
+            .regions
+            .iter_mut()
+            .filter(|region| region.address < end && address < region.range().end)
+            .collect::<Vec<_>>();
+        regions.sort_by_key(|region| region.address);
+
+        let mut cursor = address;
+        let mut spans = Vec::new();
+        for region in regions {
+            if region.address > cursor || (write && !region.writable) {
+                return Err(error);
+            }
+            let start = (cursor - region.address) as usize;
+            let stop = (end.min(region.range().end) - region.address) as usize;
+            cursor = region.address + stop as u32;
+            spans.push(&mut region.data[start..stop]);
+        }
+        if cursor != end {
+            return Err(error);
+        }
+        Ok(spans)
+    }
+
+    pub fn read(&mut self, address: u32, length: u32) -> Result<Vec<u8>, TrapReason> {
+        self.spans_mut(address, length, false).map(|spans| spans.concat())
+    }
+
+    pub fn write(&mut self, address: u32, bytes: &[u8]) -> Result<(), TrapReason> {
+        Self::copy_into(self.spans_mut(address, bytes.len() as u32, true)?, bytes);
+        Ok(())
+    }
+
+    /// Like `write`, but also into read-only regions, for setting up memory.
+    pub fn initialize(&mut self, address: u32, bytes: &[u8]) -> Result<(), TrapReason> {
+        Self::copy_into(self.spans_mut(address, bytes.len() as u32, false)?, bytes);
+        Ok(())
+    }
+
+    fn copy_into(spans: Vec<&mut [u8]>, mut bytes: &[u8]) {
+        for span in spans {
+            let (head, rest) = bytes.split_at(span.len());
+            span.copy_from_slice(head);
+            bytes = rest;
+        }
+    }
+
+    pub fn page_size(&self) -> u32 {
+        self.map.page_size()
+    }
+
+    pub fn region_of(&self, address: u32) -> Option<&MemoryRegion> {
+        self.regions.iter().find(|region| region.range().contains(&address))
+    }
+
+    /// Reads a byte without recording an access; `None` if it isn't mapped.
+    pub fn peek(&self, address: u32) -> Option<u8> {
+        let region = self.region_of(address)?;
+        region.data.get((address - region.address) as usize).copied()
+    }
+
+    /// The whole 32-bit address space split into mapped regions and the
+    /// inaccessible gaps around them, in address order.
+    pub fn segments(&self) -> Vec<MemorySegment> {
+        let mut regions = self.regions.iter().filter(|region| !region.data.is_empty()).collect::<Vec<_>>();
+        regions.sort_by_key(|region| region.address);
+
+        let mut segments = Vec::new();
+        let mut cursor = 0;
+        for region in regions {
+            // Regions laid out by hand may overlap; each byte is listed once
+            let range = region.address.max(cursor)..region.range().end;
+            if range.is_empty() {
+                continue;
+            }
+            if cursor < range.start {
+                segments.push(MemorySegment {
+                    name: "unmapped",
+                    range: cursor..range.start,
+                    access: PageAccess::Inaccessible,
+                });
+            }
+            cursor = range.end;
+            segments.push(MemorySegment {
+                name: region.name,
+                range,
+                access: if region.writable { PageAccess::ReadWrite } else { PageAccess::ReadOnly },
+            });
+        }
+        if cursor < u32::MAX {
+            segments.push(MemorySegment {
+                name: "unmapped",
+                range: cursor..u32::MAX,
+                access: PageAccess::Inaccessible,
+            });
+        }
+        segments
+    }
+
+    /// Grows the heap by `size` bytes, returning the previous top or zero on failure
+    fn sbrk(&mut self, size: u32) -> u32 {
+        let previous_top = self.heap_top;
+        if size == 0 {
+            return previous_top;
+        }
+
+        let Some(new_top) = previous_top.checked_add(size) else {
+            return 0;
+        };
+        if new_top > self.map.heap_base().saturating_add(self.map.max_heap_size()) {
+            return 0;
+        }
+
+        // Memory laid out by hand, e.g. by a test vector, may have no heap
+        let Some(heap) = self.regions.iter_mut().find(|region| region.name == "heap") else {
+            return 0;
+        };
+        heap.data.resize(new_top.saturating_sub(heap.address) as usize, 0);
+        self.heap_top = new_top;
+        previous_top
+    }
+}
+
+/// Everything the interpreter needs from a blob, detached from its bytes.
+#[derive(Clone, Debug)]
+pub struct Program {
+    pub analysis: ProgramAnalysis,
+    pub exports: Vec<(String, u32)>,
+    /// Import names, indexed by the `ecalli` immediate
+    pub imports: Vec<String>,
+    pub jump_table: Vec<u32>,
+    /// Jump table entry for each jump target that has one
+    pub jump_table_index: HashMap<u32, usize>,
+    pub ro_data: Vec<u8>,
+    pub rw_data: Vec<u8>,
+    pub memory_map: MemoryMap,
+    /// Spacing of the addresses dynamic jumps use for jump table entries
+    pub jump_alignment: u32,
+}
+
+impl Program {
+    pub fn parse(data: &[u8]) -> Result<Self, &'static str> {
+        let blob = ProgramBlob::parse(data).map_err(|_| "Failed to parse blob")?;
+        let analysis = analyze_program(&blob)?;
+        Self::from_blob(&blob, analysis)
+    }
+
+    pub fn from_blob(blob: &ProgramBlob, analysis: ProgramAnalysis) -> Result<Self, &'static str> {
+        let memory_map = MemoryMap::new(VM_MAX_PAGE_SIZE, blob.ro_data_size(), blob.rw_data_size(), blob.stack_size())?;
+        let jump_table = blob.jump_table().collect::<Vec<_>>();
+        let jump_table_index = jump_table
+            .iter()
+            .enumerate()
+            .map(|(index, &target)| (target, index))
+            .collect();
+        let exports = blob
+            .exports()
+            .map(|export| export.map(|export| (export.symbol().to_string(), export.jump_target())))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| "Failed to parse exports")?;
+        let imports = blob
+            .imports()
+            .map(|import| import.map(|import| import.symbol().to_string()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| "Failed to parse imports")?;
+
+        Ok(Program {
+            analysis,
+            exports,
+            imports,
+            jump_table,
+            jump_table_index,
+            ro_data: blob.ro_data().to_vec(),
+            rw_data: blob.rw_data().to_vec(),
+            memory_map,
+            jump_alignment: VM_CODE_ADDRESS_ALIGNMENT,
+        })
+    }
+
+    pub fn instruction_index_of(&self, jump_target: u32) -> Option<usize> {
+        self.analysis.block(jump_target).map(|block| block.instructions.start)
+    }
+
+    // Addresses of dynamic jumps are positions in the jump table, starting
+    // one alignment unit above zero so that zero is never a valid target
+    fn resolve_address(&self, address: u32) -> Option<u32> {
+        if address == 0 || address % self.jump_alignment != 0 {
+            return None;
+        }
+        let index = (address / self.jump_alignment - 1) as usize;
+        self.jump_table.get(index).copied()
+    }
+
+    fn return_address(&self, jump_target: u32) -> Option<u32> {
+        let index = *self.jump_table_index.get(&jump_target)?;
+        Some((index as u32 + 1) * self.jump_alignment)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Interpreter {
+    program: Rc<Program>,
+    regs: [u32; REG_COUNT],
+    /// Index of the next instruction to execute
+    pc: usize,
+    memory: Memory,
+    status: Status,
+    executed: u64,
+    /// Memory touched by the most recent step
+    last_accesses: Vec<MemoryAccess>,
+    host: HostEnvironment,
+    /// Unmetered when `None`
+    gas: Option<GasMeter>,
+    /// Whether each step is added to `trace`
+    tracing: bool,
+    /// The recording, kept after tracing is turned off so it can be exported
+    trace: Option<Trace>,
+    /// How many times each instruction has executed since the last reset
+    execution_counts: Vec<u64>,
+}
+
+fn sign_extend_8(value: u8) -> u32 {
+    value as i8 as i32 as u32
+}
+
+fn sign_extend_16(value: u16) -> u32 {
+    value as i16 as i32 as u32
+}
+
+fn div_unsigned(lhs: u32, rhs: u32) -> u32 {
+    if rhs == 0 {
+        u32::MAX
+    } else {
+        lhs / rhs
+    }
+}
+
+fn div_signed(lhs: u32, rhs: u32) -> u32 {
+    let (lhs, rhs) = (lhs as i32, rhs as i32);
+    if rhs == 0 {
+        u32::MAX
+    } else {
+        lhs.wrapping_div(rhs) as u32
+    }
+}
+
+fn rem_unsigned(lhs: u32, rhs: u32) -> u32 {
+    if rhs == 0 {
+        lhs
+    } else {
+        lhs % rhs
+    }
+}
+
+fn rem_signed(lhs: u32, rhs: u32) -> u32 {
+    let (lhs, rhs) = (lhs as i32, rhs as i32);
+    if rhs == 0 {
+        lhs as u32
+    } else {
+        lhs.wrapping_rem(rhs) as u32
+    }
+}
+
+fn mul_upper_signed_signed(lhs: u32, rhs: u32) -> u32 {
+    ((lhs as i32 as i64 * rhs as i32 as i64) >> 32) as u32
+}
+
+fn mul_upper_unsigned_unsigned(lhs: u32, rhs: u32) -> u32 {
+    ((lhs as u64 * rhs as u64) >> 32) as u32
+}
+
+fn mul_upper_signed_unsigned(lhs: u32, rhs: u32) -> u32 {
+    ((lhs as i32 as i64 * rhs as i64) >> 32) as u32
+}
+
+impl Interpreter {
+    pub fn new(program: Rc<Program>, entry: u32) -> Self {
+        Self::with_host_config(program, entry, HostConfig::default())
+    }
+
+    pub fn with_host_config(program: Rc<Program>, entry: u32, host_config: HostConfig) -> Self {
+        let memory = Memory::new(program.memory_map.clone(), &program.ro_data, &program.rw_data);
+        let host = HostEnvironment::new(host_config, program.imports.clone());
+        let mut interpreter = Interpreter {
+            program,
+            regs: [0; REG_COUNT],
+            pc: 0,
+            memory,
+            status: Status::Running,
+            executed: 0,
+            last_accesses: Vec::new(),
+            host,
+            gas: None,
+            tracing: false,
+            trace: None,
+            execution_counts: Vec::new(),
+        };
+        interpreter.reset(entry);
+        interpreter
+    }
+
+    /// Starts at an arbitrary instruction with the given registers and
+    /// memory, the way conformance test vectors describe initial state.
+    pub fn with_state(program: Rc<Program>, pc: usize, regs: [u32; REG_COUNT], memory: Memory) -> Self {
+        let host = HostEnvironment::new(HostConfig::default(), program.imports.clone());
+        let execution_counts = vec![0; program.analysis.instructions.len()];
+        Interpreter {
+            program,
+            regs,
+            pc,
+            memory,
+            status: Status::Running,
+            executed: 0,
+            last_accesses: Vec::new(),
+            host,
+            gas: None,
+            tracing: false,
+            trace: None,
+            execution_counts,
+        }
+    }
+
+    /// Copy of the full machine state without the recorded trace, which can
+    /// grow far larger than everything else combined.
+    pub fn snapshot(&self) -> Self {
+        Interpreter {
+            program: self.program.clone(),
+            regs: self.regs,
+            pc: self.pc,
+            memory: self.memory.clone(),
+            status: self.status.clone(),
+            executed: self.executed,
+            last_accesses: self.last_accesses.clone(),
+            host: self.host.clone(),
+            gas: self.gas.clone(),
+            tracing: self.tracing,
+            trace: None,
+            execution_counts: self.execution_counts.clone(),
+        }
+    }
+
+    /// Resets registers and memory and positions the pc at the entry point.
+    pub fn reset(&mut self, entry: u32) {
+        self.memory = Memory::new(self.program.memory_map.clone(), &self.program.ro_data, &self.program.rw_data);
+        self.regs = [0; REG_COUNT];
+        self.regs[Reg::RA as usize] = VM_ADDR_RETURN_TO_HOST;
+        self.regs[Reg::SP as usize] = self.program.memory_map.stack_address_high();
+        self.executed = 0;
+        self.last_accesses.clear();
+        self.host.clear();
+        self.execution_counts = vec![0; self.program.analysis.instructions.len()];
+        if let Some(gas) = &mut self.gas {
+            gas.refill();
+        }
+        if self.tracing {
+            self.trace = Some(Trace::default());
+        }
+        match self.program.instruction_index_of(entry) {
+            Some(pc) => {
+                self.pc = pc;
+                self.status = Status::Running;
+            }
+            None => self.status = Status::Trapped(TrapReason::InvalidJump(entry)),
+        }
+    }
+
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    pub fn regs(&self) -> &[u32; REG_COUNT] {
+        &self.regs
+    }
+
+    pub fn reg(&self, reg: Reg) -> u32 {
+        self.regs[reg as usize]
+    }
+
+    pub fn set_reg(&mut self, reg: Reg, value: u32) {
+        self.regs[reg as usize] = value;
+    }
+
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    pub fn memory(&self) -> &Memory {
+        &self.memory
+    }
+
+    pub fn memory_mut(&mut self) -> &mut Memory {
+        &mut self.memory
+    }
+
+    pub fn status(&self) -> &Status {
+        &self.status
+    }
+
+    pub fn executed(&self) -> u64 {
+        self.executed
+    }
+
+    pub fn host(&self) -> &HostEnvironment {
+        &self.host
+    }
+
+    pub fn set_host_config(&mut self, config: HostConfig) {
+        self.host.config = config;
+    }
+
+    pub fn gas(&self) -> Option<&GasMeter> {
+        self.gas.as_ref()
+    }
+
+    /// Replaces the gas meter; takes effect from the next block entered.
+    pub fn set_gas_meter(&mut self, gas: Option<GasMeter>) {
+        self.gas = gas;
+    }
+
+    pub fn execution_counts(&self) -> &[u64] {
+        &self.execution_counts
+    }
+
+    pub fn trace(&self) -> Option<&Trace> {
+        self.trace.as_ref()
+    }
+
+    /// Replaces the recorded trace, e.g. with one rewound to a snapshot.
+    pub fn set_trace(&mut self, trace: Option<Trace>) {
+        self.trace = trace;
+    }
+
+    /// Starts recording a fresh trace, or stops recording and keeps what was
+    /// recorded so far.
+    pub fn set_tracing(&mut self, enabled: bool) {
+        if enabled && !self.tracing {
+            self.trace = Some(Trace::default());
+        }
+        self.tracing = enabled;
+    }
+
+    pub fn is_tracing(&self) -> bool {
+        self.tracing
+    }
+
+    pub fn last_accesses(&self) -> &[MemoryAccess] {
+        &self.last_accesses
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.status == Status::Running
+    }
+
+    pub fn current_instruction(&self) -> Option<Instruction> {
+        self.program.analysis.instructions.get(self.pc).map(|analyzed| analyzed.instruction)
+    }
+
+    fn trap(&mut self, reason: TrapReason) {
+        self.status = Status::Trapped(reason);
+    }
+
+    fn jump_to(&mut self, jump_target: u32) {
+        match self.program.instruction_index_of(jump_target) {
+            Some(pc) => self.pc = pc,
+            None => self.trap(TrapReason::InvalidJump(jump_target)),
+        }
+    }
+
+    fn jump_to_address(&mut self, address: u32) {
+        if address == VM_ADDR_RETURN_TO_HOST {
+            self.status = Status::Halted;
+            return;
+        }
+        match self.program.resolve_address(address) {
+            Some(jump_target) => self.jump_to(jump_target),
+            None => self.trap(TrapReason::InvalidJump(address)),
+        }
+    }
+
+    // Calls return to the block following the call site
+    fn link(&mut self, ra: Reg) -> bool {
+        let Some(next_block) = self.program.analysis.block_of_instruction(self.pc).map(|block| block.jump_target + 1) else {
+            self.trap(TrapReason::InvalidInstructionPointer);
+            return false;
+        };
+        match self.program.return_address(next_block) {
+            Some(address) => {
+                self.set_reg(ra, address);
+                true
+            }
+            None => {
+                self.trap(TrapReason::InvalidJump(next_block));
+                false
+            }
+        }
+    }
+
+    fn load(&mut self, dst: Reg, address: u32, length: u32, extend: fn(&[u8]) -> u32) {
+        self.last_accesses.push(MemoryAccess { address, length, write: false });
+        match self.memory.read(address, length) {
+            Ok(bytes) => self.set_reg(dst, extend(&bytes)),
+            Err(reason) => self.trap(reason),
+        }
+    }
+
+    fn store(&mut self, address: u32, value: u32, length: usize) {
+        self.last_accesses.push(MemoryAccess {
+            address,
+            length: length as u32,
+            write: true,
+        });
+        if let Err(reason) = self.memory.write(address, &value.to_le_bytes()[..length]) {
+            self.trap(reason);
+        }
+    }
+
+    fn branch(&mut self, condition: bool, target: u32) {
+        if condition {
+            self.jump_to(target);
+        } else {
+            self.pc += 1;
+        }
+    }
+
+    /// Executes a single instruction.
+    pub fn step(&mut self) -> &Status {
+        if !self.is_running() {
+            return &self.status;
+        }
+
+        let Some(instruction) = self.current_instruction() else {
+            self.trap(TrapReason::InvalidInstructionPointer);
+            return &self.status;
+        };
+
+        if let Some(gas) = &mut self.gas {
+            let block = self.program.analysis.block_of_instruction(self.pc);
+            let jump_target = block.map_or(0, |block| block.jump_target);
+            let charged = match gas.charging {
+                Charging::PerBlock => block.map_or(true, |block| block.instructions.start != self.pc || gas.charge(jump_target)),
+                Charging::PerInstruction => gas.charge(self.pc as u32),
+            };
+            if !charged {
+                self.trap(TrapReason::OutOfGas(jump_target));
+                return &self.status;
+            }
+        }
+
+        let pc = self.pc;
+        let regs_before = self.regs;
+        self.executed += 1;
+        if let Some(count) = self.execution_counts.get_mut(pc) {
+            *count += 1;
+        }
+        self.last_accesses.clear();
+        self.execute(instruction);
+        if self.tracing {
+            self.record_trace(pc, instruction, &regs_before);
+        }
+        &self.status
+    }
+
+    fn record_trace(&mut self, pc: usize, instruction: Instruction, regs_before: &[u32; REG_COUNT]) {
+        let offset = self
+            .program
+            .analysis
+            .instructions
+            .get(pc)
+            .map_or(0, |analyzed| self.program.analysis.code_offset + analyzed.offset);
+        let register_writes = Reg::ALL
+            .into_iter()
+            .filter(|&reg| self.regs[reg as usize] != regs_before[reg as usize])
+            .map(|reg| RegisterWrite {
+                reg: reg.to_string(),
+                value: self.regs[reg as usize],
+            })
+            .collect();
+        let memory = self
+            .last_accesses
+            .iter()
+            .map(|access| TraceAccess {
+                address: access.address,
+                length: access.length,
+                write: access.write,
+            })
+            .collect();
+        let entry = TraceEntry {
+            step: self.executed,
+            pc,
+            offset,
+            opcode: opcode_name(&instruction),
+            assembly: instruction.to_string(),
+            register_writes,
+            memory,
+            gas: self.gas.as_ref().map(|gas| gas.remaining),
+        };
+        if let Some(trace) = &mut self.trace {
+            trace.push(entry);
+        }
+    }
+
+    /// Runs the emulated host function behind `ecalli index`.
+    /// Returns whether execution should continue.
+    fn execute_host_call(&mut self, index: u32) -> bool {
+        match self.host.call(index, &mut self.regs, &mut self.memory) {
+            Ok(()) => true,
+            Err(reason) => {
+                self.trap(reason);
+                false
+            }
+        }
+    }
+
+    fn execute(&mut self, instruction: Instruction) {
+        use Instruction as I;
+
+        let regs = self.regs;
+        let r = |reg: Reg| regs[reg as usize];
+        let le = |bytes: &[u8]| {
+            let mut buffer = [0u8; 4];
+            buffer[..bytes.len()].copy_from_slice(bytes);
+            u32::from_le_bytes(buffer)
+        };
+        let pc_before = self.pc;
+
+        match instruction {
+            I::trap => return self.trap(TrapReason::Trap),
+            I::fallthrough => {}
+            I::jump(target) => return self.jump_to(target),
+            I::jump_indirect(base, offset) => return self.jump_to_address(r(base).wrapping_add(offset)),
+            I::call(ra, target) => {
+                if self.link(ra) {
+                    self.jump_to(target);
+                }
+                return;
+            }
+            I::call_indirect(ra, base, offset) => {
+                let address = r(base).wrapping_add(offset);
+                if self.link(ra) {
+                    self.jump_to_address(address);
+                }
+                return;
+            }
+            I::ecalli(index) => {
+                if !self.execute_host_call(index) {
+                    return;
+                }
+            }
+
+            I::branch_eq(s1, s2, target) => return self.branch(r(s1) == r(s2), target),
+            I::branch_not_eq(s1, s2, target) => return self.branch(r(s1) != r(s2), target),
+            I::branch_less_unsigned(s1, s2, target) => return self.branch(r(s1) < r(s2), target),
+            I::branch_less_signed(s1, s2, target) => return self.branch((r(s1) as i32) < (r(s2) as i32), target),
+            I::branch_greater_or_equal_unsigned(s1, s2, target) => return self.branch(r(s1) >= r(s2), target),
+            I::branch_greater_or_equal_signed(s1, s2, target) => return self.branch(r(s1) as i32 >= r(s2) as i32, target),
+            I::branch_eq_imm(s, imm, target) => return self.branch(r(s) == imm, target),
+            I::branch_not_eq_imm(s, imm, target) => return self.branch(r(s) != imm, target),
+            I::branch_less_unsigned_imm(s, imm, target) => return self.branch(r(s) < imm, target),
+            I::branch_less_signed_imm(s, imm, target) => return self.branch((r(s) as i32) < (imm as i32), target),
+            I::branch_greater_or_equal_unsigned_imm(s, imm, target) => return self.branch(r(s) >= imm, target),
+            I::branch_greater_or_equal_signed_imm(s, imm, target) => return self.branch(r(s) as i32 >= imm as i32, target),
+            I::branch_less_or_equal_unsigned_imm(s, imm, target) => return self.branch(r(s) <= imm, target),
+            I::branch_less_or_equal_signed_imm(s, imm, target) => return self.branch(r(s) as i32 <= imm as i32, target),
+            I::branch_greater_unsigned_imm(s, imm, target) => return self.branch(r(s) > imm, target),
+            I::branch_greater_signed_imm(s, imm, target) => return self.branch(r(s) as i32 > imm as i32, target),
+
+            I::load_imm(d, imm) => self.set_reg(d, imm),
+            I::move_reg(d, s) => self.set_reg(d, r(s)),
+            I::sbrk(d, s) => {
+                let previous_top = self.memory.sbrk(r(s));
+                self.set_reg(d, previous_top);
+            }
+
+            I::load_u8(d, address) => self.load(d, address, 1, |bytes| bytes[0] as u32),
+            I::load_i8(d, address) => self.load(d, address, 1, |bytes| sign_extend_8(bytes[0])),
+            I::load_u16(d, address) => self.load(d, address, 2, |bytes| u16::from_le_bytes([bytes[0], bytes[1]]) as u32),
+            I::load_i16(d, address) => self.load(d, address, 2, |bytes| sign_extend_16(u16::from_le_bytes([bytes[0], bytes[1]]))),
+            I::load_u32(d, address) => self.load(d, address, 4, le),
+            I::load_indirect_u8(d, base, offset) => self.load(d, r(base).wrapping_add(offset), 1, |bytes| bytes[0] as u32),
+            I::load_indirect_i8(d, base, offset) => self.load(d, r(base).wrapping_add(offset), 1, |bytes| sign_extend_8(bytes[0])),
+            I::load_indirect_u16(d, base, offset) => {
+                self.load(d, r(base).wrapping_add(offset), 2, |bytes| u16::from_le_bytes([bytes[0], bytes[1]]) as u32)
+            }
+            I::load_indirect_i16(d, base, offset) => self.load(d, r(base).wrapping_add(offset), 2, |bytes| {
+                sign_extend_16(u16::from_le_bytes([bytes[0], bytes[1]]))
+            }),
+            I::load_indirect_u32(d, base, offset) => self.load(d, r(base).wrapping_add(offset), 4, le),
+
+            I::store_u8(s, address) => self.store(address, r(s), 1),
+            I::store_u16(s, address) => self.store(address, r(s), 2),
+            I::store_u32(s, address) => self.store(address, r(s), 4),
+            I::store_imm_u8(address, value) => self.store(address, value, 1),
+            I::store_imm_u16(address, value) => self.store(address, value, 2),
+            I::store_imm_u32(address, value) => self.store(address, value, 4),
+            I::store_indirect_u8(s, base, offset) => self.store(r(base).wrapping_add(offset), r(s), 1),
+            I::store_indirect_u16(s, base, offset) => self.store(r(base).wrapping_add(offset), r(s), 2),
+            I::store_indirect_u32(s, base, offset) => self.store(r(base).wrapping_add(offset), r(s), 4),
+            I::store_imm_indirect_u8(base, offset, value) => self.store(r(base).wrapping_add(offset), value, 1),
+            I::store_imm_indirect_u16(base, offset, value) => self.store(r(base).wrapping_add(offset), value, 2),
+            I::store_imm_indirect_u32(base, offset, value) => self.store(r(base).wrapping_add(offset), value, 4),
+
+            I::add(d, s1, s2) => self.set_reg(d, r(s1).wrapping_add(r(s2))),
+            I::add_imm(d, s, imm) => self.set_reg(d, r(s).wrapping_add(imm)),
+            I::sub(d, s1, s2) => self.set_reg(d, r(s1).wrapping_sub(r(s2))),
+            I::negate_and_add_imm(d, s, imm) => self.set_reg(d, imm.wrapping_sub(r(s))),
+            I::and(d, s1, s2) => self.set_reg(d, r(s1) & r(s2)),
+            I::and_imm(d, s, imm) => self.set_reg(d, r(s) & imm),
+            I::or(d, s1, s2) => self.set_reg(d, r(s1) | r(s2)),
+            I::or_imm(d, s, imm) => self.set_reg(d, r(s) | imm),
+            I::xor(d, s1, s2) => self.set_reg(d, r(s1) ^ r(s2)),
+            I::xor_imm(d, s, imm) => self.set_reg(d, r(s) ^ imm),
+            I::mul(d, s1, s2) => self.set_reg(d, r(s1).wrapping_mul(r(s2))),
+            I::mul_imm(d, s, imm) => self.set_reg(d, r(s).wrapping_mul(imm)),
+            I::mul_upper_signed_signed(d, s1, s2) => self.set_reg(d, mul_upper_signed_signed(r(s1), r(s2))),
+            I::mul_upper_signed_signed_imm(d, s, imm) => self.set_reg(d, mul_upper_signed_signed(r(s), imm)),
+            I::mul_upper_unsigned_unsigned(d, s1, s2) => self.set_reg(d, mul_upper_unsigned_unsigned(r(s1), r(s2))),
+            I::mul_upper_unsigned_unsigned_imm(d, s, imm) => self.set_reg(d, mul_upper_unsigned_unsigned(r(s), imm)),
+            I::mul_upper_signed_unsigned(d, s1, s2) => self.set_reg(d, mul_upper_signed_unsigned(r(s1), r(s2))),
+            I::div_unsigned(d, s1, s2) => self.set_reg(d, div_unsigned(r(s1), r(s2))),
+            I::div_signed(d, s1, s2) => self.set_reg(d, div_signed(r(s1), r(s2))),
+            I::rem_unsigned(d, s1, s2) => self.set_reg(d, rem_unsigned(r(s1), r(s2))),
+            I::rem_signed(d, s1, s2) => self.set_reg(d, rem_signed(r(s1), r(s2))),
+            I::set_less_than_unsigned(d, s1, s2) => self.set_reg(d, (r(s1) < r(s2)) as u32),
+            I::set_less_than_signed(d, s1, s2) => self.set_reg(d, ((r(s1) as i32) < (r(s2) as i32)) as u32),
+            I::set_less_than_unsigned_imm(d, s, imm) => self.set_reg(d, (r(s) < imm) as u32),
+            I::set_less_than_signed_imm(d, s, imm) => self.set_reg(d, ((r(s) as i32) < (imm as i32)) as u32),
+            I::set_greater_than_unsigned_imm(d, s, imm) => self.set_reg(d, (r(s) > imm) as u32),
+            I::set_greater_than_signed_imm(d, s, imm) => self.set_reg(d, (r(s) as i32 > imm as i32) as u32),
+            I::shift_logical_left(d, s1, s2) => self.set_reg(d, r(s1).wrapping_shl(r(s2))),
+            I::shift_logical_right(d, s1, s2) => self.set_reg(d, r(s1).wrapping_shr(r(s2))),
+            I::shift_arithmetic_right(d, s1, s2) => self.set_reg(d, (r(s1) as i32).wrapping_shr(r(s2)) as u32),
+            I::shift_logical_left_imm(d, s, imm) => self.set_reg(d, r(s).wrapping_shl(imm)),
+            I::shift_logical_right_imm(d, s, imm) => self.set_reg(d, r(s).wrapping_shr(imm)),
+            I::shift_arithmetic_right_imm(d, s, imm) => self.set_reg(d, (r(s) as i32).wrapping_shr(imm) as u32),
+            I::shift_logical_left_imm_alt(d, s, imm) => self.set_reg(d, imm.wrapping_shl(r(s))),
+            I::shift_logical_right_imm_alt(d, s, imm) => self.set_reg(d, imm.wrapping_shr(r(s))),
+            I::shift_arithmetic_right_imm_alt(d, s, imm) => self.set_reg(d, (imm as i32).wrapping_shr(r(s)) as u32),
+            I::cmov_if_zero(d, s, c) => {
+                if r(c) == 0 {
+                    self.set_reg(d, r(s));
+                }
+            }
+            I::cmov_if_not_zero(d, s, c) => {
+                if r(c) != 0 {
+                    self.set_reg(d, r(s));
+                }
+            }
+
+            _ => return self.trap(TrapReason::Unimplemented(instruction.to_string())),
+        }
+
+        if !self.is_running() || self.pc != pc_before {
+            return;
+        }
+
+        self.pc += 1;
+    }
+
+    /// Runs until `should_stop` returns true before an instruction executes,
+    /// execution stops on its own, or the step budget runs out.
+    pub fn run_until(&mut self, mut should_stop: impl FnMut(&Interpreter) -> bool) -> &Status {
+        for _ in 0..MAX_STEPS_PER_RUN {
+            if !self.is_running() {
+                break;
+            }
+            self.step();
+            if self.is_running() && should_stop(self) {
+                break;
+            }
+        }
+        &self.status
+    }
+
+    pub fn run(&mut self) -> &Status {
+        self.run_until(|_| false)
+    }
+
+    pub fn run_to(&mut self, index: usize) -> &Status {
+        self.run_until(|interpreter| interpreter.pc == index)
+    }
+
+    /// Steps over calls: runs until execution returns to the block after the
+    /// call with the stack unwound back to where it was.
+    pub fn step_over(&mut self) -> &Status {
+        self.step_over_until(|_| false)
+    }
+
+    /// Like `step_over`, but also stops early when `should_stop` says so.
+    pub fn step_over_until(&mut self, mut should_stop: impl FnMut(&Interpreter) -> bool) -> &Status {
+        let is_call = self
+            .current_instruction()
+            .map_or(false, |instruction| matches!(control_flow(&instruction), ControlFlow::Call(_) | ControlFlow::IndirectCall));
+        if !is_call {
+            return self.step();
+        }
+
+        let return_pc = self
+            .program
+            .analysis
+            .block_of_instruction(self.pc)
+            .and_then(|block| self.program.analysis.block(block.jump_target + 1))
+            .map(|block| block.instructions.start);
+        let stack_pointer = self.reg(Reg::SP);
+        match return_pc {
+            Some(return_pc) => self.run_until(|interpreter| {
+                (interpreter.pc == return_pc && interpreter.reg(Reg::SP) >= stack_pointer) || should_stop(interpreter)
+            }),
+            None => self.step(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn memory() -> Memory {
+        let map = MemoryMap::new(0x1000, 0, 16, 0x1000).unwrap();
+        Memory::new(map, &[], &[1; 16])
+    }
+
+    #[test]
+    fn heap_starts_after_the_last_rw_data_page() {
+        let mut memory = memory();
+        let rw_end = memory.map.rw_data_range().end;
+        assert_eq!(memory.sbrk(0x1000), memory.map.heap_base());
+        assert_eq!(memory.heap_top, memory.map.heap_base() + 0x1000);
+
+        let heap = memory.regions.iter().find(|region| region.name == "heap").unwrap();
+        assert_eq!(heap.range(), rw_end..memory.heap_top);
+    }
+
+    #[test]
+    fn access_across_the_rw_data_end_reads_back_what_was_written() {
+        let mut memory = memory();
+        let rw_end = memory.map.rw_data_range().end;
+        memory.sbrk(0x1000);
+
+        memory.write(rw_end - 4, &[1, 2, 3, 4, 5, 6, 7, 8]).unwrap();
+        assert_eq!(memory.read(rw_end - 4, 8).unwrap(), [1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(memory.read(rw_end - 2, 4).unwrap(), [3, 4, 5, 6]);
+        assert_eq!(memory.read(rw_end, 4).unwrap(), [5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn segments_list_each_mapped_byte_once() {
+        let mut memory = memory();
+        let rw_data = memory.map.rw_data_range();
+        memory.sbrk(0x1000);
+        memory.write(rw_data.end, &[7]).unwrap();
+
+        let segments = memory.segments();
+        for pair in segments.windows(2) {
+            assert_eq!(pair[0].range.end, pair[1].range.start);
+        }
+        let heap = segments.iter().find(|segment| segment.name == "heap").unwrap();
+        assert_eq!(heap.range, rw_data.end..memory.heap_top);
+        assert_eq!(segments.iter().find(|segment| segment.name == "rw_data").unwrap().range, rw_data);
+        assert_eq!(memory.peek(rw_data.end), Some(7));
+    }
+
+    #[test]
+    fn stopping_tracing_keeps_the_recording() {
+        // add a2, a0, a1; trap
+        let program = Rc::new(crate::raw_program::parse(&[0, 0, 3, 8, 0x87, 9, 0b1]).unwrap());
+        let memory = Memory::from_regions(program.memory_map.clone(), Vec::new());
+        let mut interpreter = Interpreter::with_state(program, 0, [0; REG_COUNT], memory);
+        let recorded = |interpreter: &Interpreter| interpreter.trace().map(|trace| trace.entries.len());
+
+        interpreter.set_tracing(true);
+        interpreter.step();
+        interpreter.set_tracing(false);
+        interpreter.step();
+        assert_eq!(recorded(&interpreter), Some(1));
+
+        interpreter.set_tracing(true);
+        assert_eq!(recorded(&interpreter), Some(0));
+    }
+
+    #[test]
+    fn access_running_past_the_heap_top_traps() {
+        let mut memory = memory();
+        let rw_end = memory.map.rw_data_range().end;
+        assert_eq!(
+            memory.read(rw_end - 2, 4),
+            Err(TrapReason::MemoryAccess { address: rw_end - 2, write: false })
+        );
+    }
+}
@@ -3,3 +3,32 @@
 #[path = "home.rs"] pub mod home;
 #[path = "disassembler.rs"] pub mod disassembler;
 #[path = "file_upload.rs"] pub mod file_upload;
+#[path = "blob_structure.rs"] pub mod blob_structure;
+#[path = "symbols.rs"] pub mod symbols;
+pub use polkarun_core::analysis;
+#[path = "cfg.rs"] pub mod cfg;
+#[path = "xrefs.rs"] pub mod xrefs;
+#[path = "interpreter.rs"] pub mod interpreter;
+#[path = "emulator.rs"] pub mod emulator;
+#[path = "storage.rs"] pub mod storage;
+#[path = "breakpoints.rs"] pub mod breakpoints;
+#[path = "host.rs"] pub mod host;
+#[path = "memory_view.rs"] pub mod memory_view;
+#[path = "gas.rs"] pub mod gas;
+#[path = "trace.rs"] pub mod trace;
+#[path = "trace_view.rs"] pub mod trace_view;
+#[path = "coverage.rs"] pub mod coverage;
+#[path = "coverage_view.rs"] pub mod coverage_view;
+#[path = "history.rs"] pub mod history;
+#[path = "raw_program.rs"] pub mod raw_program;
+#[path = "test_vectors.rs"] pub mod test_vectors;
+#[path = "conformance.rs"] pub mod conformance;
+#[path = "blob_diff.rs"] pub mod blob_diff;
+#[path = "compare.rs"] pub mod compare;
+#[path = "database.rs"] pub mod database;
+#[path = "recent_files.rs"] pub mod recent_files;
+#[path = "fetch.rs"] pub mod fetch;
+#[path = "samples.rs"] pub mod samples;
+#[path = "menu.rs"] pub mod menu;
+#[path = "appearance.rs"] pub mod appearance;
+#[path = "settings.rs"] pub mod settings;
@@ -0,0 +1,249 @@
+use crate::breakpoints::parse_number;
+use crate::interpreter::{Interpreter, MemorySegment, PageAccess};
+use leptos::*;
+use polkavm_common::program::Reg;
+
+const BYTES_PER_ROW: u32 = 16;
+const VISIBLE_ROWS: u32 = 32;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct MemoryRow {
+    address: u32,
+    /// `None` for bytes in inaccessible pages
+    bytes: Vec<Option<u8>>,
+    /// Written by the last step
+    changed: Vec<bool>,
+}
+
+fn printable(byte: u8) -> char {
+    if (32..=126).contains(&byte) {
+        byte as char
+    } else {
+        '.'
+    }
+}
+
+fn memory_rows(interpreter: &Interpreter, start: u32) -> Vec<MemoryRow> {
+    let memory = interpreter.memory();
+    let writes = interpreter
+        .last_accesses()
+        .iter()
+        .filter(|access| access.write)
+        .collect::<Vec<_>>();
+
+    (0..VISIBLE_ROWS)
+        .filter_map(|row| start.checked_add(row * BYTES_PER_ROW))
+        .map(|address| {
+            let addresses = (0..BYTES_PER_ROW).filter_map(|column| address.checked_add(column));
+            MemoryRow {
+                address,
+                bytes: addresses.clone().map(|address| memory.peek(address)).collect(),
+                changed: addresses
+                    .map(|address| writes.iter().any(|access| access.overlaps(&(address..address.saturating_add(1)))))
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+fn access_label(access: PageAccess) -> &'static str {
+    match access {
+        PageAccess::ReadOnly => "r-",
+        PageAccess::ReadWrite => "rw",
+        PageAccess::Inaccessible => "--",
+    }
+}
+
+#[component]
+fn SegmentList<F: Fn(u32) + Copy + 'static>(segments: Memo<Vec<MemorySegment>>, page_size: Memo<u32>, on_segment_selected: F) -> impl IntoView {
+    view! {
+        <table class="w-full text-xs font-mono">
+            <thead>
+                <tr class="text-left bg-gray-200">
+                    <th class="p-1">"Region"</th>
+                    <th class="p-1">"Range"</th>
+                    <th class="p-1">"Pages"</th>
+                    <th class="p-1">"Access"</th>
+                </tr>
+            </thead>
+            <tbody>
+                {move || {
+                    let page_size = page_size().max(1);
+                    segments()
+                        .into_iter()
+                        .map(|segment| {
+                            let start = segment.range.start;
+                            let pages = (segment.range.len() as u64).div_ceil(u64::from(page_size));
+                            let mapped = segment.access != PageAccess::Inaccessible;
+                            view! {
+                                <tr
+                                    class="border-t border-gray-200 cursor-pointer hover:bg-gray-200"
+                                    class:text-gray-400=!mapped
+                                    on:click=move |_| on_segment_selected(start)
+                                >
+                                    <td class="p-1">{segment.name}</td>
+                                    <td class="p-1">{format!("0x{:08x}..0x{:08x}", segment.range.start, segment.range.end)}</td>
+                                    <td class="p-1">{pages.to_string()}</td>
+                                    <td class="p-1">{access_label(segment.access)}</td>
+                                </tr>
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                }}
+            </tbody>
+        </table>
+    }
+}
+
+/// Hex view of the guest address space with the memory map beside it.
+#[component]
+pub fn MemoryInspector(interpreter: RwSignal<Option<Interpreter>>) -> impl IntoView {
+    let (view_address, set_view_address) = create_signal(0u32);
+    let (target_address, set_target_address) = create_signal(None::<u32>);
+    let (address_input, set_address_input) = create_signal(String::new());
+    let (address_error, set_address_error) = create_signal(None::<String>);
+
+    let segments = create_memo(move |_| {
+        interpreter.with(|vm| vm.as_ref().map(|vm| vm.memory().segments()).unwrap_or_default())
+    });
+    let page_size = create_memo(move |_| interpreter.with(|vm| vm.as_ref().map_or(0, |vm| vm.memory().page_size())));
+    let rows = create_memo(move |_| {
+        let start = view_address();
+        interpreter.with(|vm| vm.as_ref().map(|vm| memory_rows(vm, start)).unwrap_or_default())
+    });
+
+    let go_to = move |address: u32| {
+        set_view_address(address - address % BYTES_PER_ROW);
+        set_target_address(Some(address));
+    };
+
+    // Starts out on the stack pointer, where most of the action is
+    create_effect(move |previous: Option<bool>| {
+        let loaded = interpreter.with(Option::is_some);
+        if loaded && previous != Some(true) {
+            if let Some(sp) = interpreter.with_untracked(|vm| vm.as_ref().map(|vm| vm.reg(Reg::SP))) {
+                go_to(sp.saturating_sub(VISIBLE_ROWS * BYTES_PER_ROW / 2));
+                set_target_address(None);
+            }
+        }
+        loaded
+    });
+
+    let on_go = move |_| match parse_number(&address_input.get_untracked()) {
+        Some(address) => {
+            set_address_error(None);
+            go_to(address);
+        }
+        None => set_address_error(Some(format!("Invalid address '{}'", address_input.get_untracked()))),
+    };
+
+    let page_span = VISIBLE_ROWS * BYTES_PER_ROW;
+    let button_class = "px-2 py-1 text-xs font-semibold text-gray-700 bg-white hover:bg-gray-100 rounded-xs border border-gray-300";
+
+    view! {
+        <div class="flex flex-row w-full px-4 pb-4 gap-4">
+            <div class="w-2/3 h-50vh overflow-auto border border-gray-200 rounded">
+                <div class="flex items-center gap-2 p-2 bg-gray-200">
+                    <span class="font-bold">"Memory"</span>
+                    <input
+                        type="text"
+                        class="w-32 px-1 text-sm font-mono border border-gray-300"
+                        placeholder="0x00020000"
+                        prop:value=address_input
+                        on:input=move |event| set_address_input(event_target_value(&event))
+                        on:keydown=move |event| {
+                            if event.key() == "Enter" {
+                                on_go(());
+                            }
+                        }
+                    />
+                    <button class=button_class on:click=move |_| on_go(())>"Go"</button>
+                    <button class=button_class on:click=move |_| set_view_address.update(|address| *address = address.saturating_sub(page_span))>
+                        "Previous"
+                    </button>
+                    <button class=button_class on:click=move |_| set_view_address.update(|address| *address = address.saturating_add(page_span))>
+                        "Next"
+                    </button>
+                    {move || address_error().map(|message| view! { <span class="text-xs text-red-600">{message}</span> })}
+                </div>
+                <pre class="p-2 font-mono text-xs md:text-md">
+                    {move || {
+                        let target = target_address();
+                        rows()
+                            .into_iter()
+                            .map(|row| {
+                                let hex = row
+                                    .bytes
+                                    .iter()
+                                    .zip(&row.changed)
+                                    .enumerate()
+                                    .map(|(column, (byte, &changed))| {
+                                        let address = row.address.wrapping_add(column as u32);
+                                        let text = byte.map_or_else(|| "--".to_string(), |byte| format!("{:02x}", byte));
+                                        view! {
+                                            <span
+                                                class:text-gray-400=byte.is_none()
+                                                class:bg-orange-200=changed
+                                                class:ring-1=target == Some(address)
+                                            >
+                                                {text}
+                                            </span>
+                                            " "
+                                        }
+                                    })
+                                    .collect::<Vec<_>>();
+                                let text = row
+                                    .bytes
+                                    .iter()
+                                    .zip(&row.changed)
+                                    .map(|(byte, &changed)| {
+                                        let character = byte.map_or(' ', printable);
+                                        view! { <span class:bg-orange-200=changed>{character.to_string()}</span> }
+                                    })
+                                    .collect::<Vec<_>>();
+                                let mapped = row.bytes.iter().any(Option::is_some);
+                                view! {
+                                    <div class:bg-gray-100=!mapped>
+                                        {format!("{:08x} ", row.address)}
+                                        {hex}
+                                        {text}
+                                    </div>
+                                }
+                            })
+                            .collect::<Vec<_>>()
+                    }}
+                </pre>
+            </div>
+            <div class="w-1/3 h-50vh overflow-auto border border-gray-200 rounded bg-gray-50">
+                <div class="p-2 font-bold bg-gray-200">"Address space"</div>
+                <div class="px-2 py-1 text-xs text-gray-600">
+                    {move || format!("Page size 0x{:x}; unmapped pages trap on access", page_size())}
+                </div>
+                <SegmentList segments=segments page_size=page_size on_segment_selected=go_to/>
+                <div class="p-2 font-bold bg-gray-200">"Last step"</div>
+                <div class="text-xs font-mono">
+                    {move || {
+                        interpreter.with(|vm| {
+                            vm.as_ref()
+                                .map(|vm| {
+                                    vm.last_accesses()
+                                        .iter()
+                                        .map(|access| {
+                                            let address = access.address;
+                                            let kind = if access.write { "write" } else { "read" };
+                                            view! {
+                                                <div class="px-2 py-1 cursor-pointer hover:bg-gray-200" on:click=move |_| go_to(address)>
+                                                    {format!("{} {} bytes at 0x{:08x}", kind, access.length, address)}
+                                                </div>
+                                            }
+                                        })
+                                        .collect::<Vec<_>>()
+                                })
+                                .unwrap_or_default()
+                        })
+                    }}
+                </div>
+            </div>
+        </div>
+    }
+}
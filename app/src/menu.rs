@@ -0,0 +1,334 @@
+use leptos::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::rc::Rc;
+
+const DISASSEMBLER_MENU: &str = include_str!("../public/ron/disassembler.ron");
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum MenuItemType {
+    RegularItem,
+    SubMenu { items: Vec<MenuItem> },
+    /// Navigates to another page
+    Link(String),
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MenuItem {
+    pub label: String,
+    pub item_type: MenuItemType,
+    /// Id of the registered handler run when the item is clicked. Items
+    /// without one are shown disabled.
+    #[serde(default)]
+    pub action: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MainMenu {
+    pub items: Vec<MenuItem>,
+}
+
+impl MainMenu {
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        ron::from_str(contents).map_err(|error| format!("Invalid menu: {}", error))
+    }
+
+    pub fn disassembler() -> Result<Self, String> {
+        Self::parse(DISASSEMBLER_MENU)
+    }
+
+    /// Every item of the menu and its submenus, depth first.
+    pub fn all_items(&self) -> Vec<&MenuItem> {
+        fn collect<'a>(items: &'a [MenuItem], all: &mut Vec<&'a MenuItem>) {
+            for item in items {
+                all.push(item);
+                if let MenuItemType::SubMenu { items: children } = &item.item_type {
+                    collect(children, all);
+                }
+            }
+        }
+
+        let mut all = Vec::new();
+        collect(&self.items, &mut all);
+        all
+    }
+}
+
+// Byte ranges of the `{name}` placeholders in a label, braces included
+fn placeholders(label: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut position = 0;
+    while let Some(start) = label[position..].find('{').map(|start| position + start) {
+        let Some(end) = label[start..].find('}').map(|end| start + end + 1) else {
+            break;
+        };
+        ranges.push(start..end);
+        position = end;
+    }
+    ranges
+}
+
+/// Handlers for menu actions, keyed by the action ids used in the menu file,
+/// and the values filling `{name}` placeholders in labels.
+#[derive(Clone, Default)]
+pub struct MenuActions {
+    handlers: HashMap<&'static str, Rc<dyn Fn()>>,
+    values: HashMap<&'static str, Rc<dyn Fn() -> String>>,
+}
+
+impl MenuActions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(mut self, action: &'static str, handler: impl Fn() + 'static) -> Self {
+        self.handlers.insert(action, Rc::new(handler));
+        self
+    }
+
+    /// Reactive value shown in place of `{name}`.
+    pub fn value(mut self, name: &'static str, value: impl Fn() -> String + 'static) -> Self {
+        self.values.insert(name, Rc::new(value));
+        self
+    }
+
+    fn label(&self, label: String) -> impl Fn() -> String + 'static {
+        let values = self.values.clone();
+        move || {
+            let mut filled = String::new();
+            let mut position = 0;
+            for range in placeholders(&label) {
+                filled.push_str(&label[position..range.start]);
+                let name = &label[range.start + 1..range.end - 1];
+                match values.get(name) {
+                    Some(value) => filled.push_str(&value()),
+                    None => filled.push_str(&label[range.clone()]),
+                }
+                position = range.end;
+            }
+            filled.push_str(&label[position..]);
+            filled
+        }
+    }
+
+    pub fn dispatch(&self, action: &str) {
+        match self.handlers.get(action) {
+            Some(handler) => handler(),
+            None => log::error!("No handler for menu action {}", action),
+        }
+    }
+
+    /// Errors listing the actions and placeholders of `menu` that nothing
+    /// was registered for.
+    pub fn check(&self, menu: &MainMenu) -> Result<(), String> {
+        let mut unknown = Vec::new();
+        for item in menu.all_items() {
+            if let Some(action) = item.action.as_deref().filter(|action| !self.handlers.contains_key(*action)) {
+                unknown.push(format!("action {}", action));
+            }
+            for range in placeholders(&item.label) {
+                let name = &item.label[range.start + 1..range.end - 1];
+                if !self.values.contains_key(name) {
+                    unknown.push(format!("placeholder {{{}}}", name));
+                }
+            }
+        }
+
+        if unknown.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("Menu entries without a handler: {}", unknown.join(", ")))
+        }
+    }
+}
+
+// Entry of a dropdown; submenus open to the side
+fn menu_entry(item: MenuItem, actions: MenuActions, close: WriteSignal<bool>) -> View {
+    let class = "block px-3 py-1 whitespace-nowrap cursor-pointer hover:bg-gray-100 dark:hover:bg-gray-700";
+    let label = actions.label(item.label);
+    match item.item_type {
+        MenuItemType::Link(href) => view! {
+            <li role="none">
+                <a role="menuitem" class=class href=href on:click=move |_| close(false)>
+                    {label}
+                </a>
+            </li>
+        }
+        .into_view(),
+        MenuItemType::SubMenu { items: children } => {
+            let (open, set_open) = create_signal(false);
+            view! {
+                <li
+                    role="menuitem"
+                    aria-haspopup="menu"
+                    class="relative"
+                    on:mouseenter=move |_| set_open(true)
+                    on:mouseleave=move |_| set_open(false)
+                >
+                    <div class=class on:click=move |_| set_open.update(|open| *open = !*open)>
+                        {label} " ▸"
+                    </div>
+                    <Show when=open>
+                        {dropdown(children.clone(), actions.clone(), close, "left-full top-0")}
+                    </Show>
+                </li>
+            }
+            .into_view()
+        }
+        MenuItemType::RegularItem => {
+            let action = item.action;
+            let disabled = action.is_none();
+            view! {
+                <li
+                    role="menuitem"
+                    aria-disabled=disabled.to_string()
+                    class=class
+                    class:text-gray-400=disabled
+                    on:click=move |_| {
+                        if let Some(action) = &action {
+                            close(false);
+                            actions.dispatch(action);
+                        }
+                    }
+                >
+                    {label}
+                </li>
+            }
+            .into_view()
+        }
+    }
+}
+
+fn dropdown(items: Vec<MenuItem>, actions: MenuActions, close: WriteSignal<bool>, position: &str) -> View {
+    view! {
+        <ul
+            role="menu"
+            class=format!("absolute {} z-10 min-w-40 list-none p-1 m-0 bg-white border border-gray-300 rounded-xs shadow-md text-sm dark:bg-gray-900 dark:border-gray-600", position)
+        >
+            {items
+                .into_iter()
+                .map(|item| menu_entry(item, actions.clone(), close))
+                .collect::<Vec<_>>()}
+        </ul>
+    }
+    .into_view()
+}
+
+#[component]
+fn MenuButton(item: MenuItem, actions: MenuActions) -> impl IntoView {
+    let class = "menu-button px-4 py-2 text-md font-semibold text-gray-700 bg-white hover:bg-gray-100 focus:bg-gray-200 rounded-xs border border-gray-300 shadow-sm cursor-pointer focus:outline-none focus:ring-2 focus:ring-blue-500 focus:ring-opacity-50 dark:text-gray-200 dark:bg-gray-900 dark:hover:bg-gray-700 dark:focus:bg-gray-600 dark:border-gray-600";
+    let (open, set_open) = create_signal(false);
+    let label = actions.label(item.label);
+
+    match item.item_type {
+        MenuItemType::Link(href) => view! {
+            <a role="menuitem" class=class href=href>
+                {label}
+            </a>
+        }
+        .into_view(),
+        MenuItemType::SubMenu { items } => view! {
+            <div class="relative">
+                <div role="menuitem" aria-haspopup="menu" class=class on:click=move |_| set_open.update(|open| *open = !*open)>
+                    {label}
+                </div>
+                <Show when=open>
+                    {dropdown(items.clone(), actions.clone(), set_open, "left-0 mt-1")}
+                </Show>
+            </div>
+        }
+        .into_view(),
+        MenuItemType::RegularItem => {
+            let action = item.action;
+            let disabled = action.is_none();
+            view! {
+                <div
+                    role="menuitem"
+                    aria-disabled=disabled.to_string()
+                    class=class
+                    class:text-gray-400=disabled
+                    on:click=move |_| {
+                        if let Some(action) = &action {
+                            actions.dispatch(action);
+                        }
+                    }
+                >
+                    {label}
+                </div>
+            }
+            .into_view()
+        }
+    }
+}
+
+/// Menu bar built from `menu`. Panics if the menu names an action `actions`
+/// has no handler for, so the menu file and the code can't drift apart.
+#[component]
+pub fn MenuBar(menu: MainMenu, actions: MenuActions) -> impl IntoView {
+    if let Err(error) = actions.check(&menu) {
+        panic!("{}", error);
+    }
+
+    view! {
+        <nav
+            role="menubar"
+            class="flex h-10 items-center space-x-1 rounded-md border-0 bg-background p-1"
+            tabindex="0"
+            data-orientation="horizontal"
+            style="outline:none"
+        >
+            {menu
+                .items
+                .into_iter()
+                .map(|item| view! { <MenuButton item=item actions=actions.clone()/> })
+                .collect::<Vec<_>>()}
+        </nav>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MENU: &str = r#"
+        #![enable(implicit_some)]
+        MainMenu(
+            items: [
+                (
+                    label: "View",
+                    item_type: SubMenu(
+                        items: [
+                            (label: "Zoom: {zoom_level}%", item_type: RegularItem),
+                            (label: "Zoom in", item_type: RegularItem, action: "zoom_in"),
+                        ]
+                    )
+                ),
+                (label: "Compare", item_type: Link("/compare")),
+            ],
+        )
+    "#;
+
+    #[test]
+    fn parses_nested_submenus() {
+        let menu = MainMenu::parse(MENU).unwrap();
+        let labels = menu.all_items().iter().map(|item| item.label.as_str()).collect::<Vec<_>>();
+        assert_eq!(labels, ["View", "Zoom: {zoom_level}%", "Zoom in", "Compare"]);
+        assert_eq!(menu.all_items()[2].action.as_deref(), Some("zoom_in"));
+    }
+
+    #[test]
+    fn check_reports_unhandled_actions_and_placeholders() {
+        let menu = MainMenu::parse(MENU).unwrap();
+        assert_eq!(
+            MenuActions::new().check(&menu),
+            Err("Menu entries without a handler: placeholder {zoom_level}, action zoom_in".to_string())
+        );
+
+        let actions = MenuActions::new()
+            .register("zoom_in", || {})
+            .value("zoom_level", || "100".to_string());
+        assert_eq!(actions.check(&menu), Ok(()));
+        assert_eq!(actions.label("Zoom: {zoom_level}% {other}".to_string())(), "Zoom: 100% {other}");
+    }
+}
@@ -59,7 +59,7 @@ fn GithubSVG() -> impl IntoView {
 #[component]
 pub fn Navigation() -> impl IntoView {
     view! {
-        <div class="bg-#552BBF flex justify-between">
+        <div class="bg-#552BBF dark:bg-#2E1766 flex justify-between">
             <div>
                 <a
                     href="/"
@@ -73,6 +73,18 @@ pub fn Navigation() -> impl IntoView {
                 >
                     Disassembler
                 </a>
+                <a
+                    href="/emulator"
+                    class="inline-block px-4 py-2 text-#D3FF33 hover:bg-#421E9D  hover:text-#E1FF66"
+                >
+                    Emulator
+                </a>
+                <a
+                    href="/conformance"
+                    class="inline-block px-4 py-2 text-#D3FF33 hover:bg-#421E9D  hover:text-#E1FF66"
+                >
+                    Conformance
+                </a>
             </div>
             <div class="hidden md:block">
                 <a
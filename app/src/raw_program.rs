@@ -0,0 +1,344 @@
+use crate::analysis::{analyze_instructions, control_flow, AnalyzedInstruction, ControlFlow};
+use crate::interpreter::{Program, REG_COUNT};
+use polkarun_core::sections::read_varint;
+use polkavm_common::abi::{MemoryMap, VM_MAX_PAGE_SIZE};
+use polkavm_common::program::{Instruction, Reg};
+use std::collections::HashMap;
+
+/// Dynamic jumps go to jump table entry `index` through address `(index + 1) * 2`
+const JUMP_ALIGNMENT: u32 = 2;
+const MAX_IMMEDIATE_LENGTH: usize = 4;
+/// Longest run of argument bytes an instruction may have
+const MAX_SKIP: usize = 24;
+
+/// Target of a jump or branch that doesn't start a basic block; jumping
+/// there traps.
+const INVALID_TARGET: u32 = u32::MAX;
+
+struct Code<'a> {
+    bytes: &'a [u8],
+    bitmask: &'a [u8],
+}
+
+impl Code<'_> {
+    // The code is implicitly followed by zeroes, i.e. by `trap`s
+    fn byte(&self, offset: usize) -> u8 {
+        self.bytes.get(offset).copied().unwrap_or(0)
+    }
+
+    fn is_instruction_start(&self, offset: usize) -> bool {
+        offset >= self.bytes.len() || self.bitmask[offset / 8] & (1 << (offset % 8)) != 0
+    }
+
+    // Number of argument bytes following the opcode at `offset`
+    fn skip(&self, offset: usize) -> usize {
+        (0..MAX_SKIP).find(|&skip| self.is_instruction_start(offset + 1 + skip)).unwrap_or(MAX_SKIP)
+    }
+
+    // Little-endian and sign-extended from however many bytes it has
+    fn immediate(&self, offset: usize, length: usize) -> u32 {
+        let length = length.min(MAX_IMMEDIATE_LENGTH);
+        if length == 0 {
+            return 0;
+        }
+        let value = (0..length).fold(0u32, |value, index| value | u32::from(self.byte(offset + index)) << (index * 8));
+        let unused_bits = 32 - 8 * length as u32;
+        ((value << unused_bits) as i32 >> unused_bits) as u32
+    }
+}
+
+fn reg(index: u8) -> Reg {
+    Reg::ALL[usize::from(index).min(REG_COUNT - 1)]
+}
+
+// Offsets of jumps and branches are relative to the instruction
+fn relative(offset: usize, immediate: u32) -> usize {
+    (offset as u32).wrapping_add(immediate) as usize
+}
+
+fn decode(code: &Code, offset: usize, target: &dyn Fn(usize) -> u32) -> Result<Instruction, String> {
+    use Instruction as I;
+
+    let skip = code.skip(offset);
+    let registers = code.byte(offset + 1);
+    let (low, high) = (registers & 0x0f, registers >> 4);
+
+    let imm = || code.immediate(offset + 1, skip);
+    let target_at = |immediate: u32| target(relative(offset, immediate));
+
+    // Two immediates, the length of the first in the low bits of the first byte
+    let imm_imm = |constructor: fn(u32, u32) -> Instruction| {
+        let x_length = usize::from(registers & 0b111).min(MAX_IMMEDIATE_LENGTH);
+        let y_length = skip.saturating_sub(x_length + 1);
+        constructor(code.immediate(offset + 2, x_length), code.immediate(offset + 2 + x_length, y_length))
+    };
+    let reg_imm = |constructor: fn(Reg, u32) -> Instruction| constructor(reg(low), code.immediate(offset + 2, skip.saturating_sub(1)));
+    // A register and two immediates, the length of the first in the high nibble
+    let reg_imm_imm = |constructor: fn(Reg, u32, u32) -> Instruction, y_is_offset: bool| {
+        let x_length = usize::from(high & 0b111).min(MAX_IMMEDIATE_LENGTH);
+        let y_length = skip.saturating_sub(x_length + 1);
+        let y = code.immediate(offset + 2 + x_length, y_length);
+        constructor(reg(low), code.immediate(offset + 2, x_length), if y_is_offset { target_at(y) } else { y })
+    };
+    let reg_reg_imm = |constructor: fn(Reg, Reg, u32) -> Instruction| {
+        constructor(reg(low), reg(high), code.immediate(offset + 2, skip.saturating_sub(1)))
+    };
+    let reg_reg_offset = |constructor: fn(Reg, Reg, u32) -> Instruction| {
+        constructor(reg(low), reg(high), target_at(code.immediate(offset + 2, skip.saturating_sub(1))))
+    };
+    // The destination comes last in the encoding
+    let reg_reg_reg = |constructor: fn(Reg, Reg, Reg) -> Instruction| constructor(reg(code.byte(offset + 2)), reg(low), reg(high));
+
+    let instruction = match code.byte(offset) {
+        0 => I::trap,
+        17 => I::fallthrough,
+
+        78 => I::ecalli(imm()),
+        5 => I::jump(target_at(imm())),
+
+        // The address comes first, then the value
+        62 => imm_imm(I::store_imm_u8),
+        79 => imm_imm(I::store_imm_u16),
+        38 => imm_imm(I::store_imm_u32),
+
+        19 => reg_imm(I::jump_indirect),
+        4 => reg_imm(I::load_imm),
+        60 => reg_imm(I::load_u8),
+        74 => reg_imm(I::load_i8),
+        76 => reg_imm(I::load_u16),
+        66 => reg_imm(I::load_i16),
+        10 => reg_imm(I::load_u32),
+        71 => reg_imm(I::store_u8),
+        69 => reg_imm(I::store_u16),
+        22 => reg_imm(I::store_u32),
+
+        26 => reg_imm_imm(I::store_imm_indirect_u8, false),
+        54 => reg_imm_imm(I::store_imm_indirect_u16, false),
+        13 => reg_imm_imm(I::store_imm_indirect_u32, false),
+        7 => reg_imm_imm(I::branch_eq_imm, true),
+        15 => reg_imm_imm(I::branch_not_eq_imm, true),
+        44 => reg_imm_imm(I::branch_less_unsigned_imm, true),
+        32 => reg_imm_imm(I::branch_less_signed_imm, true),
+        52 => reg_imm_imm(I::branch_greater_or_equal_unsigned_imm, true),
+        45 => reg_imm_imm(I::branch_greater_or_equal_signed_imm, true),
+        46 => reg_imm_imm(I::branch_less_or_equal_signed_imm, true),
+        59 => reg_imm_imm(I::branch_less_or_equal_unsigned_imm, true),
+        53 => reg_imm_imm(I::branch_greater_signed_imm, true),
+        50 => reg_imm_imm(I::branch_greater_unsigned_imm, true),
+
+        82 => I::move_reg(reg(low), reg(high)),
+        87 => I::sbrk(reg(low), reg(high)),
+
+        16 => reg_reg_imm(I::store_indirect_u8),
+        29 => reg_reg_imm(I::store_indirect_u16),
+        3 => reg_reg_imm(I::store_indirect_u32),
+        11 => reg_reg_imm(I::load_indirect_u8),
+        21 => reg_reg_imm(I::load_indirect_i8),
+        37 => reg_reg_imm(I::load_indirect_u16),
+        33 => reg_reg_imm(I::load_indirect_i16),
+        1 => reg_reg_imm(I::load_indirect_u32),
+        2 => reg_reg_imm(I::add_imm),
+        18 => reg_reg_imm(I::and_imm),
+        31 => reg_reg_imm(I::xor_imm),
+        49 => reg_reg_imm(I::or_imm),
+        35 => reg_reg_imm(I::mul_imm),
+        65 => reg_reg_imm(I::mul_upper_signed_signed_imm),
+        63 => reg_reg_imm(I::mul_upper_unsigned_unsigned_imm),
+        27 => reg_reg_imm(I::set_less_than_unsigned_imm),
+        56 => reg_reg_imm(I::set_less_than_signed_imm),
+        9 => reg_reg_imm(I::shift_logical_left_imm),
+        14 => reg_reg_imm(I::shift_logical_right_imm),
+        25 => reg_reg_imm(I::shift_arithmetic_right_imm),
+        40 => reg_reg_imm(I::negate_and_add_imm),
+        39 => reg_reg_imm(I::set_greater_than_unsigned_imm),
+        61 => reg_reg_imm(I::set_greater_than_signed_imm),
+        72 => reg_reg_imm(I::shift_logical_right_imm_alt),
+        80 => reg_reg_imm(I::shift_arithmetic_right_imm_alt),
+        75 => reg_reg_imm(I::shift_logical_left_imm_alt),
+        85 => reg_reg_imm(I::cmov_if_zero_imm),
+        86 => reg_reg_imm(I::cmov_if_not_zero_imm),
+
+        24 => reg_reg_offset(I::branch_eq),
+        30 => reg_reg_offset(I::branch_not_eq),
+        47 => reg_reg_offset(I::branch_less_unsigned),
+        48 => reg_reg_offset(I::branch_less_signed),
+        41 => reg_reg_offset(I::branch_greater_or_equal_unsigned),
+        43 => reg_reg_offset(I::branch_greater_or_equal_signed),
+
+        8 => reg_reg_reg(I::add),
+        20 => reg_reg_reg(I::sub),
+        23 => reg_reg_reg(I::and),
+        28 => reg_reg_reg(I::xor),
+        12 => reg_reg_reg(I::or),
+        34 => reg_reg_reg(I::mul),
+        67 => reg_reg_reg(I::mul_upper_signed_signed),
+        57 => reg_reg_reg(I::mul_upper_unsigned_unsigned),
+        81 => reg_reg_reg(I::mul_upper_signed_unsigned),
+        36 => reg_reg_reg(I::set_less_than_unsigned),
+        58 => reg_reg_reg(I::set_less_than_signed),
+        55 => reg_reg_reg(I::shift_logical_left),
+        51 => reg_reg_reg(I::shift_logical_right),
+        77 => reg_reg_reg(I::shift_arithmetic_right),
+        68 => reg_reg_reg(I::div_unsigned),
+        64 => reg_reg_reg(I::div_signed),
+        73 => reg_reg_reg(I::rem_unsigned),
+        70 => reg_reg_reg(I::rem_signed),
+        83 => reg_reg_reg(I::cmov_if_zero),
+        84 => reg_reg_reg(I::cmov_if_not_zero),
+
+        // The JAM PVM puts `load_imm_jump` and `load_imm_jump_ind` where
+        // polkavm has `call` and `call_indirect`, which work differently
+        6 => return Err(format!("Unsupported instruction load_imm_jump at offset {}", offset)),
+        42 => return Err(format!("Unsupported instruction load_imm_jump_ind at offset {}", offset)),
+
+        // Unknown opcodes trap
+        _ => I::trap,
+    };
+    Ok(instruction)
+}
+
+fn take<'a>(data: &'a [u8], position: &mut usize, length: usize, what: &str) -> Result<&'a [u8], String> {
+    let bytes = position
+        .checked_add(length)
+        .and_then(|end| data.get(*position..end))
+        .ok_or_else(|| format!("Truncated program: {} doesn't fit", what))?;
+    *position += length;
+    Ok(bytes)
+}
+
+fn decode_all(code: &Code, target: &dyn Fn(usize) -> u32) -> Result<Vec<AnalyzedInstruction>, String> {
+    let mut instructions = (0..code.bytes.len())
+        .filter(|&offset| code.is_instruction_start(offset))
+        .map(|offset| {
+            Ok(AnalyzedInstruction {
+                offset,
+                length: 1 + code.skip(offset),
+                instruction: decode(code, offset, target)?,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    // Running off the end of the code executes the `trap` of the padding
+    instructions.push(AnalyzedInstruction {
+        offset: code.bytes.len(),
+        length: 1,
+        instruction: Instruction::trap,
+    });
+    Ok(instructions)
+}
+
+/// Parses a program in the raw format of the JAM PVM test vectors: a jump
+/// table, the code and a bitmask marking the bytes that start an
+/// instruction, without the sections of a .polkavm container. Jump targets
+/// are byte offsets into the code and become the block indices the
+/// interpreter uses.
+pub fn parse(data: &[u8]) -> Result<Program, String> {
+    let mut position = 0;
+    let jump_table_length = read_varint(data, &mut position).ok_or("Truncated program: no jump table length")? as usize;
+    let entry_size = usize::from(*data.get(position).ok_or("Truncated program: no jump table entry size")?);
+    position += 1;
+    if entry_size > 4 {
+        return Err(format!("Jump table entries of {} bytes are too large", entry_size));
+    }
+    let code_length = read_varint(data, &mut position).ok_or("Truncated program: no code length")? as usize;
+
+    let jump_table_bytes = take(data, &mut position, jump_table_length.saturating_mul(entry_size), "jump table")?;
+    let code = Code {
+        bytes: take(data, &mut position, code_length, "code")?,
+        bitmask: take(data, &mut position, code_length.div_ceil(8), "bitmask")?,
+    };
+    if position != data.len() {
+        return Err(format!("{} unexpected bytes after the bitmask", data.len() - position));
+    }
+
+    // Blocks start at the beginning of the code and after every instruction
+    // ending one; what they are doesn't depend on where jumps go
+    let decoded = decode_all(&code, &|_| INVALID_TARGET)?;
+    let block_starts = std::iter::once(0)
+        .chain(
+            decoded
+                .iter()
+                .filter(|analyzed| control_flow(&analyzed.instruction) != ControlFlow::Continue)
+                .map(|analyzed| analyzed.offset + analyzed.length),
+        )
+        .enumerate()
+        .map(|(jump_target, offset)| (offset, jump_target as u32))
+        .collect::<HashMap<_, _>>();
+    let target = |offset: usize| block_starts.get(&offset).copied().unwrap_or(INVALID_TARGET);
+
+    let instructions = decode_all(&code, &target)?;
+    let jump_table = (0..jump_table_length)
+        .map(|index| {
+            let entry = &jump_table_bytes[index * entry_size..(index + 1) * entry_size];
+            target(entry.iter().rev().fold(0, |offset, &byte| offset << 8 | usize::from(byte)))
+        })
+        .collect::<Vec<_>>();
+    let jump_table_index = jump_table
+        .iter()
+        .enumerate()
+        .map(|(index, &target)| (target, index))
+        .collect();
+
+    Ok(Program {
+        analysis: analyze_instructions(instructions, &[], &jump_table),
+        exports: Vec::new(),
+        imports: Vec::new(),
+        jump_table,
+        jump_table_index,
+        ro_data: Vec::new(),
+        rw_data: Vec::new(),
+        memory_map: MemoryMap::new(VM_MAX_PAGE_SIZE, 0, 0, 0)?,
+        jump_alignment: JUMP_ALIGNMENT,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instructions(program: &Program) -> Vec<(usize, Instruction)> {
+        program
+            .analysis
+            .instructions
+            .iter()
+            .map(|analyzed| (analyzed.offset, analyzed.instruction))
+            .collect()
+    }
+
+    #[test]
+    fn decodes_registers_and_pads_with_trap() {
+        // add a2, a0, a1
+        let program = parse(&[0, 0, 3, 8, 0x87, 9, 0b1]).unwrap();
+        assert_eq!(
+            instructions(&program),
+            [(0, Instruction::add(Reg::A2, Reg::A0, Reg::A1)), (3, Instruction::trap)]
+        );
+    }
+
+    #[test]
+    fn turns_offsets_into_jump_targets() {
+        // 0: branch_eq_imm a0, -1, 4
+        // 4: trap
+        // 5: fallthrough
+        // 6: branch_eq a0, a1, 2
+        // with a jump table pointing at the fallthrough
+        let program = parse(&[1, 1, 9, 5, 7, 0x17, 0xff, 4, 0, 17, 24, 0x87, 0xfc, 0b0111_0001, 0b0]).unwrap();
+        assert_eq!(
+            instructions(&program),
+            [
+                (0, Instruction::branch_eq_imm(Reg::A0, u32::MAX, 1)),
+                (4, Instruction::trap),
+                (5, Instruction::fallthrough),
+                (6, Instruction::branch_eq(Reg::A0, Reg::A1, INVALID_TARGET)),
+                (9, Instruction::trap),
+            ]
+        );
+        assert_eq!(program.jump_table, [2]);
+    }
+
+    #[test]
+    fn rejects_truncated_programs() {
+        assert!(parse(&[0, 0, 3, 8, 0x87]).is_err());
+        assert!(parse(&[0, 0, 1, 0, 0b1, 0]).is_err());
+    }
+}
@@ -0,0 +1,70 @@
+use crate::database::{forget_file, recent_files, StoredFile};
+use leptos::*;
+use wasm_bindgen::JsValue;
+
+fn opened_at(file: &StoredFile) -> String {
+    js_sys::Date::new(&JsValue::from_f64(file.last_opened))
+        .to_locale_string("default", &JsValue::UNDEFINED)
+        .into()
+}
+
+/// Files kept in IndexedDB, each linking to the Disassembler, which restores
+/// them without the network.
+#[component]
+pub fn RecentFiles(
+    /// Bumped by the caller when it has stored another file
+    #[prop(optional)]
+    refresh: Option<ReadSignal<usize>>,
+) -> impl IntoView {
+    let (forgotten, set_forgotten) = create_signal(0usize);
+    let files = create_local_resource(
+        move || (refresh.map(|refresh| refresh()), forgotten()),
+        |_| async {
+            recent_files().await.unwrap_or_else(|error| {
+                log::warn!("{}", error);
+                Vec::new()
+            })
+        },
+    );
+
+    view! {
+        <Show when=move || files().map_or(false, |files| !files.is_empty())>
+            <div class="p-2 lg:p-4 mt-2 bg-gray-100 w-full shadow-md text-sm dark:bg-gray-800">
+                <div class="font-bold mb-1">"Recent files"</div>
+                {move || {
+                    files()
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|file| {
+                            let hash = file.hash.clone();
+                            view! {
+                                <div class="flex items-center hover:bg-gray-200 p-1 dark:hover:bg-gray-600" title=opened_at(&file)>
+                                    <a class="flex flex-1 items-center min-w-0" href=format!("/disassembler?src={}", file.hash)>
+                                        <div class="i-mdi-history" />
+                                        <span class="truncate">{file.filename.clone()}</span>
+                                        <span class="ml-auto pl-2 text-xs text-gray-500">{format!("{} B", file.size)}</span>
+                                    </a>
+                                    <button
+                                        class="ml-1 text-gray-500 hover:text-gray-900 dark:hover:text-gray-100"
+                                        title="Forget"
+                                        on:click=move |_| {
+                                            let hash = hash.clone();
+                                            spawn_local(async move {
+                                                if let Err(error) = forget_file(&hash).await {
+                                                    log::warn!("{}", error);
+                                                }
+                                                set_forgotten.update(|forgotten| *forgotten += 1);
+                                            });
+                                        }
+                                    >
+                                        <div class="i-mdi-close" />
+                                    </button>
+                                </div>
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                }}
+            </div>
+        </Show>
+    }
+}
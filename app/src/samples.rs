@@ -0,0 +1,56 @@
+use leptos::*;
+use serde::Deserialize;
+
+const SAMPLES: &str = include_str!("../public/samples/samples.ron");
+
+/// A program bundled under `public/samples`.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct Sample {
+    pub name: String,
+    pub file: String,
+    pub description: String,
+    /// Source code of the program
+    pub source: String,
+}
+
+impl Sample {
+    pub fn url(&self) -> String {
+        format!("/samples/{}", self.file)
+    }
+}
+
+pub fn samples() -> Result<Vec<Sample>, String> {
+    ron::from_str(SAMPLES).map_err(|error| format!("Invalid sample list: {}", error))
+}
+
+/// One-click links opening each sample in the Disassembler.
+#[component]
+pub fn SampleGallery() -> impl IntoView {
+    let samples = samples().unwrap_or_else(|error| {
+        log::error!("{}", error);
+        Vec::new()
+    });
+
+    view! {
+        <div class="p-2 lg:p-4 mt-2 bg-gray-100 w-full shadow-md text-sm dark:bg-gray-800">
+            <div class="font-bold mb-1">"Samples"</div>
+            {samples
+                .into_iter()
+                .map(|sample| {
+                    let href = format!("/disassembler?src={}", String::from(js_sys::encode_uri_component(&sample.url())));
+                    view! {
+                        <div class="flex items-center hover:bg-gray-200 p-1 dark:hover:bg-gray-600">
+                            <a class="flex flex-1 flex-col min-w-0" href=href>
+                                <span class="flex items-center"><div class="i-mdi-file" />{sample.name}</span>
+                                <span class="text-xs text-gray-500 truncate">{sample.description}</span>
+                            </a>
+                            <a class="ml-1 text-xs text-gray-500 hover:text-gray-900 dark:hover:text-gray-100" href=sample.source title="Source">
+                                <div class="i-mdi-open-in-new" />
+                            </a>
+                        </div>
+                    }
+                })
+                .collect::<Vec<_>>()}
+        </div>
+    }
+}
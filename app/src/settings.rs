@@ -0,0 +1,159 @@
+use leptos::*;
+use polkarun_core::listing::{ListingFormat, OffsetBase};
+use serde::{Deserialize, Serialize};
+use crate::storage;
+
+const STORAGE_KEY: &str = "polkarun.settings";
+
+/// Limit on the hex dump's bytes per row
+const MAX_BYTES_PER_ROW: usize = 64;
+const MAX_OFFSET_WIDTH: usize = 16;
+
+/// Which columns of the disassembly listing are shown.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Columns {
+    pub offset: bool,
+    pub hex: bool,
+    pub assembly: bool,
+    pub operation: bool,
+}
+
+impl Default for Columns {
+    fn default() -> Self {
+        Self {
+            offset: true,
+            hex: true,
+            assembly: true,
+            operation: true,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Settings {
+    pub format: ListingFormat,
+    pub columns: Columns,
+}
+
+/// Loads the stored display settings and saves them whenever they change.
+pub fn provide_settings() {
+    let settings = create_rw_signal(storage::load::<Settings>(STORAGE_KEY).unwrap_or_default());
+    create_effect(move |_| storage::save(STORAGE_KEY, &settings()));
+    provide_context(settings);
+}
+
+pub fn use_settings() -> RwSignal<Settings> {
+    expect_context()
+}
+
+fn checkbox(label: &'static str, checked: impl Fn() -> bool + 'static, set: impl Fn(bool) + 'static) -> View {
+    view! {
+        <label class="flex items-center gap-1">
+            <input type="checkbox" prop:checked=checked on:change=move |event| set(event_target_checked(&event))/>
+            {label}
+        </label>
+    }
+    .into_view()
+}
+
+/// Display preferences for the hex dump and the disassembly listing. Changes
+/// apply as they are made.
+#[component]
+pub fn SettingsDialog<F: Fn() + Copy + 'static>(on_close: F) -> impl IntoView {
+    let settings = use_settings();
+    let format = move || settings().format;
+    let columns = move || settings().columns;
+    let update_format = move |update: &dyn Fn(&mut ListingFormat)| settings.update(|settings| update(&mut settings.format));
+    let update_columns = move |update: &dyn Fn(&mut Columns)| settings.update(|settings| update(&mut settings.columns));
+
+    view! {
+        <div class="fixed inset-0 z-20 flex items-center justify-center bg-black/40" on:click=move |_| on_close()>
+            <div
+                role="dialog"
+                aria-label="Settings"
+                class="w-96 p-4 text-sm bg-white border border-gray-300 rounded shadow-md dark:bg-gray-900 dark:border-gray-600"
+                on:click=|event| event.stop_propagation()
+            >
+                <div class="flex items-center mb-2 font-bold">
+                    "Settings"
+                    <button class="ml-auto text-gray-500 hover:text-gray-900 dark:hover:text-gray-100" title="Close" on:click=move |_| on_close()>
+                        <div class="i-mdi-close" />
+                    </button>
+                </div>
+
+                <div class="font-bold mt-2 mb-1">"Hex dump"</div>
+                <label class="flex items-center gap-2">
+                    "Bytes per row"
+                    <input
+                        type="number"
+                        min="1"
+                        max=MAX_BYTES_PER_ROW.to_string()
+                        class="w-16 px-1 border border-gray-300 font-mono dark:bg-gray-900 dark:border-gray-600"
+                        prop:value=move || format().bytes_per_row.to_string()
+                        on:change=move |event| {
+                            if let Ok(bytes_per_row) = event_target_value(&event).parse::<usize>() {
+                                update_format(&|format| format.bytes_per_row = bytes_per_row.clamp(1, MAX_BYTES_PER_ROW));
+                            }
+                        }
+                    />
+                </label>
+                {checkbox("Upper-case hex", move || format().uppercase_hex, move |uppercase_hex| {
+                    update_format(&|format| format.uppercase_hex = uppercase_hex)
+                })}
+
+                <div class="font-bold mt-2 mb-1">"Offsets"</div>
+                <label class="flex items-center gap-2">
+                    "Base"
+                    <select
+                        class="h-7 px-1 border border-gray-300 bg-white dark:bg-gray-900 dark:border-gray-600"
+                        on:change=move |event| {
+                            let offset_base = match event_target_value(&event).as_str() {
+                                "decimal" => OffsetBase::Decimal,
+                                _ => OffsetBase::Hexadecimal,
+                            };
+                            update_format(&|format| format.offset_base = offset_base);
+                        }
+                    >
+                        <option value="hexadecimal" selected=move || format().offset_base == OffsetBase::Hexadecimal>"Hexadecimal"</option>
+                        <option value="decimal" selected=move || format().offset_base == OffsetBase::Decimal>"Decimal"</option>
+                    </select>
+                </label>
+                <label class="flex items-center gap-2 mt-1">
+                    "Width"
+                    <input
+                        type="number"
+                        min="0"
+                        max=MAX_OFFSET_WIDTH.to_string()
+                        class="w-16 px-1 border border-gray-300 font-mono dark:bg-gray-900 dark:border-gray-600"
+                        prop:value=move || format().offset_width.to_string()
+                        on:change=move |event| {
+                            if let Ok(offset_width) = event_target_value(&event).parse::<usize>() {
+                                update_format(&|format| format.offset_width = offset_width.min(MAX_OFFSET_WIDTH));
+                            }
+                        }
+                    />
+                </label>
+
+                <div class="font-bold mt-2 mb-1">"Disassembly"</div>
+                {checkbox("Signed immediates", move || format().signed_immediates, move |signed_immediates| {
+                    update_format(&|format| format.signed_immediates = signed_immediates)
+                })}
+                <div class="flex flex-wrap gap-x-3 mt-1">
+                    {checkbox("Offset", move || columns().offset, move |offset| update_columns(&|columns| columns.offset = offset))}
+                    {checkbox("Hex", move || columns().hex, move |hex| update_columns(&|columns| columns.hex = hex))}
+                    {checkbox("Assembly", move || columns().assembly, move |assembly| update_columns(&|columns| columns.assembly = assembly))}
+                    {checkbox("Operation", move || columns().operation, move |operation| update_columns(&|columns| columns.operation = operation))}
+                </div>
+
+                <button
+                    class="mt-3 px-3 py-1 font-semibold text-gray-700 bg-white hover:bg-gray-100 rounded-xs border border-gray-300 dark:bg-gray-900 dark:hover:bg-gray-700 dark:border-gray-600 dark:text-gray-200"
+                    on:click=move |_| settings.set(Settings::default())
+                >
+                    "Reset to defaults"
+                </button>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,47 @@
+use serde::{de::DeserializeOwned, Serialize};
+use web_sys::Storage;
+
+fn local_storage() -> Option<Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+// FNV-1a; enough to tell uploaded files apart, not meant to be collision resistant
+pub fn content_hash(data: &[u8]) -> String {
+    let hash = data.iter().fold(0xcbf29ce484222325u64, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}", hash)
+}
+
+pub fn is_content_hash(text: &str) -> bool {
+    text.len() == 16 && text.bytes().all(|byte| byte.is_ascii_hexdigit())
+}
+
+pub fn load<T: DeserializeOwned>(key: &str) -> Option<T> {
+    let value = local_storage()?.get_item(key).ok()??;
+    ron::from_str(&value)
+        .map_err(|error| log::warn!("Ignoring stored {}: {}", key, error))
+        .ok()
+}
+
+pub fn save<T: Serialize>(key: &str, value: &T) {
+    let Some(storage) = local_storage() else {
+        return;
+    };
+    match ron::to_string(value) {
+        Ok(serialized) => {
+            if storage.set_item(key, &serialized).is_err() {
+                log::warn!("Failed to store {}", key);
+            }
+        }
+        Err(error) => log::warn!("Failed to serialize {}: {}", key, error),
+    }
+}
+
+pub fn remove(key: &str) {
+    if let Some(storage) = local_storage() {
+        if storage.remove_item(key).is_err() {
+            log::warn!("Failed to remove {}", key);
+        }
+    }
+}
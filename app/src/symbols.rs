@@ -0,0 +1,67 @@
+use leptos::*;
+use std::rc::Rc;
+pub use polkarun_core::symbols::{program_symbols, ExportSymbol, ImportSymbol, ProgramSymbols};
+
+#[component]
+pub fn SymbolTable<F: Fn(ExportSymbol) + 'static>(
+    symbols: ReadSignal<ProgramSymbols>,
+    on_export_selected: F,
+) -> impl IntoView {
+    let on_export_selected = Rc::new(on_export_selected);
+
+    view! {
+        <div class="mt-4 p-2 bg-gray-100 w-full shadow-md text-xs dark:bg-gray-800">
+            <div class="font-bold pb-1">"Exports"</div>
+            <table class="w-full table-fixed font-mono">
+                <thead>
+                    <tr class="text-left bg-gray-200 dark:bg-gray-700">
+                        <th class="p-1">"Name"</th>
+                        <th class="p-1 w-12">"Address"</th>
+                    </tr>
+                </thead>
+                <tbody>
+                    <For
+                        each=move || symbols().exports.into_iter()
+                        key=|export| (export.name.clone(), export.jump_target)
+                        children=move |export| {
+                            let on_export_selected = on_export_selected.clone();
+                            let selected_export = export.clone();
+                            view! {
+                                <tr
+                                    class="cursor-pointer hover:bg-gray-200 dark:hover:bg-gray-600"
+                                    on:click=move |_| on_export_selected(selected_export.clone())
+                                >
+                                    <td class="p-1 truncate" title=export.name.clone()>{export.name.clone()}</td>
+                                    <td class="p-1">{format!("@{}", export.jump_target)}</td>
+                                </tr>
+                            }
+                        }
+                    />
+                </tbody>
+            </table>
+            <div class="font-bold pt-2 pb-1">"Imports"</div>
+            <table class="w-full table-fixed font-mono">
+                <thead>
+                    <tr class="text-left bg-gray-200 dark:bg-gray-700">
+                        <th class="p-1 w-12">"Index"</th>
+                        <th class="p-1">"Name"</th>
+                    </tr>
+                </thead>
+                <tbody>
+                    <For
+                        each=move || symbols().imports.into_iter()
+                        key=|import| import.index
+                        children=move |import| {
+                            view! {
+                                <tr>
+                                    <td class="p-1">{import.index}</td>
+                                    <td class="p-1 truncate" title=import.name.clone()>{import.name.clone()}</td>
+                                </tr>
+                            }
+                        }
+                    />
+                </tbody>
+            </table>
+        </div>
+    }
+}
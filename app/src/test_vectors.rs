@@ -0,0 +1,242 @@
+use crate::gas::{GasMeter, UnitCost};
+use crate::interpreter::{Interpreter, Memory, MemoryRegion, Status, TrapReason, REG_COUNT};
+use crate::raw_program;
+use serde::Deserialize;
+use std::rc::Rc;
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct PageMapEntry {
+    pub address: u32,
+    pub length: u32,
+    pub is_writable: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct MemoryChunk {
+    pub address: u32,
+    pub contents: Vec<u8>,
+}
+
+/// A PVM conformance test vector in the community JSON schema.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TestVector {
+    pub name: String,
+    pub initial_regs: Vec<u32>,
+    /// Byte offset into the code
+    pub initial_pc: u32,
+    #[serde(default)]
+    pub initial_page_map: Vec<PageMapEntry>,
+    #[serde(default)]
+    pub initial_memory: Vec<MemoryChunk>,
+    pub initial_gas: i64,
+    /// Jump table, code and bitmask, see `raw_program::parse`
+    pub program: Vec<u8>,
+    pub expected_status: String,
+    pub expected_regs: Vec<u32>,
+    pub expected_pc: u32,
+    #[serde(default)]
+    pub expected_memory: Vec<MemoryChunk>,
+    pub expected_gas: i64,
+}
+
+/// Accepts a file holding either a single vector or an array of them.
+pub fn parse_vectors(contents: &str) -> Result<Vec<TestVector>, String> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(Box<TestVector>),
+        Many(Vec<TestVector>),
+    }
+
+    match serde_json::from_str(contents).map_err(|error| format!("Invalid test vector: {}", error))? {
+        OneOrMany::One(vector) => Ok(vec![*vector]),
+        OneOrMany::Many(vectors) => Ok(vectors),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldDiff {
+    pub field: String,
+    pub expected: String,
+    pub actual: String,
+}
+
+/// Outcome of one group of fields, one column of the pass/fail matrix.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Check {
+    pub name: &'static str,
+    pub diffs: Vec<FieldDiff>,
+}
+
+impl Check {
+    pub fn passed(&self) -> bool {
+        self.diffs.is_empty()
+    }
+}
+
+pub const CHECK_NAMES: [&str; 5] = ["status", "regs", "pc", "memory", "gas"];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VectorOutcome {
+    Ran(Vec<Check>),
+    /// The vector couldn't be set up, e.g. an unparsable program
+    Error(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VectorResult {
+    pub file: String,
+    pub name: String,
+    pub outcome: VectorOutcome,
+}
+
+impl VectorResult {
+    pub fn passed(&self) -> bool {
+        match &self.outcome {
+            VectorOutcome::Ran(checks) => checks.iter().all(Check::passed),
+            VectorOutcome::Error(_) => false,
+        }
+    }
+}
+
+// Status names used by the vectors; "trap" and "panic" are the same thing
+// under two generations of the schema
+fn status_name(status: &Status) -> &'static str {
+    match status {
+        Status::Running => "running",
+        Status::Halted => "halt",
+        Status::Trapped(TrapReason::MemoryAccess { .. }) => "page-fault",
+        Status::Trapped(TrapReason::OutOfGas(_)) => "out-of-gas",
+        Status::Trapped(TrapReason::UnhandledHostCall(_)) => "host",
+        Status::Trapped(_) => "panic",
+    }
+}
+
+fn normalize_status(status: &str) -> &str {
+    match status {
+        "trap" => "panic",
+        status => status,
+    }
+}
+
+fn setup(vector: &TestVector) -> Result<Interpreter, String> {
+    let program = raw_program::parse(&vector.program).map_err(|error| format!("Invalid program: {}", error))?;
+
+    let pc = program
+        .analysis
+        .instructions
+        .iter()
+        .position(|analyzed| analyzed.offset == vector.initial_pc as usize)
+        .ok_or_else(|| format!("No instruction at initial pc {}", vector.initial_pc))?;
+
+    if vector.initial_regs.len() != REG_COUNT {
+        return Err(format!("Expected {} initial registers, got {}", REG_COUNT, vector.initial_regs.len()));
+    }
+    let mut regs = [0; REG_COUNT];
+    regs.copy_from_slice(&vector.initial_regs);
+
+    let regions = vector
+        .initial_page_map
+        .iter()
+        .map(|page| MemoryRegion {
+            name: "page",
+            address: page.address,
+            data: vec![0; page.length as usize],
+            writable: page.is_writable,
+        })
+        .collect();
+    let mut memory = Memory::from_regions(program.memory_map.clone(), regions);
+    for chunk in &vector.initial_memory {
+        memory
+            .initialize(chunk.address, &chunk.contents)
+            .map_err(|_| format!("Initial memory at 0x{:08x} is outside the page map", chunk.address))?;
+    }
+
+    // The JAM PVM charges one gas for every instruction
+    let program = Rc::new(program);
+    let gas = GasMeter::per_instruction(&UnitCost, &program.analysis, vector.initial_gas.max(0) as u64);
+    let mut interpreter = Interpreter::with_state(program, pc, regs, memory);
+    interpreter.set_gas_meter(Some(gas));
+    Ok(interpreter)
+}
+
+fn check_final_state(vector: &TestVector, interpreter: &Interpreter) -> Vec<Check> {
+    let diff = |field: String, expected: String, actual: String| FieldDiff { field, expected, actual };
+
+    let mut status = Vec::new();
+    let actual_status = status_name(interpreter.status());
+    if normalize_status(&vector.expected_status) != actual_status {
+        status.push(diff("status".to_string(), vector.expected_status.clone(), actual_status.to_string()));
+    }
+
+    let regs = (0..vector.expected_regs.len().max(REG_COUNT))
+        .filter_map(|index| {
+            let expected = vector.expected_regs.get(index).copied();
+            let actual = interpreter.regs().get(index).copied();
+            (expected != actual).then(|| {
+                let show = |value: Option<u32>| value.map_or_else(|| "-".to_string(), |value| format!("0x{:x}", value));
+                diff(format!("r{}", index), show(expected), show(actual))
+            })
+        })
+        .collect();
+
+    let analysis = &interpreter.program().analysis;
+    let actual_pc = analysis
+        .instructions
+        .get(interpreter.pc())
+        .map_or_else(|| analysis.instructions.last().map_or(0, |last| last.offset + last.length), |analyzed| analyzed.offset);
+    let mut pc = Vec::new();
+    if vector.expected_pc as usize != actual_pc {
+        pc.push(diff("pc".to_string(), vector.expected_pc.to_string(), actual_pc.to_string()));
+    }
+
+    let memory = vector
+        .expected_memory
+        .iter()
+        .filter_map(|chunk| {
+            let actual = (0..chunk.contents.len() as u32)
+                .map(|offset| interpreter.memory().peek(chunk.address.wrapping_add(offset)))
+                .collect::<Vec<_>>();
+            let matches = actual.iter().zip(&chunk.contents).all(|(actual, expected)| *actual == Some(*expected));
+            (!matches).then(|| {
+                let actual = actual
+                    .iter()
+                    .map(|byte| byte.map_or_else(|| "--".to_string(), |byte| format!("{:02x}", byte)))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                let expected = chunk.contents.iter().map(|byte| format!("{:02x}", byte)).collect::<Vec<_>>().join(" ");
+                diff(format!("memory 0x{:08x}", chunk.address), expected, actual)
+            })
+        })
+        .collect();
+
+    let mut gas = Vec::new();
+    let actual_gas = interpreter.gas().map_or(0, |gas| gas.remaining as i64);
+    if vector.expected_gas != actual_gas {
+        gas.push(diff("gas".to_string(), vector.expected_gas.to_string(), actual_gas.to_string()));
+    }
+
+    [status, regs, pc, memory, gas]
+        .into_iter()
+        .zip(CHECK_NAMES)
+        .map(|(diffs, name)| Check { name, diffs })
+        .collect()
+}
+
+pub fn run_vector(file: &str, vector: &TestVector) -> VectorResult {
+    let outcome = match setup(vector) {
+        Ok(mut interpreter) => {
+            interpreter.run();
+            VectorOutcome::Ran(check_final_state(vector, &interpreter))
+        }
+        Err(message) => VectorOutcome::Error(message),
+    };
+
+    VectorResult {
+        file: file.to_string(),
+        name: vector.name.clone(),
+        outcome,
+    }
+}
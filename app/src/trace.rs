@@ -0,0 +1,274 @@
+use serde::{Deserialize, Serialize};
+
+// Keeps a runaway trace from eating all of the tab's memory
+pub const MAX_TRACE_ENTRIES: usize = 1_000_000;
+
+const CSV_HEADER: &str = "step,pc,offset,opcode,assembly,register_writes,memory,gas";
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RegisterWrite {
+    pub reg: String,
+    pub value: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TraceAccess {
+    pub address: u32,
+    pub length: u32,
+    pub write: bool,
+}
+
+/// One executed instruction and its effects.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TraceEntry {
+    /// 1-based count of executed instructions
+    pub step: u64,
+    /// Instruction index in the code section
+    pub pc: usize,
+    /// Byte offset of the instruction in the blob
+    pub offset: usize,
+    pub opcode: String,
+    pub assembly: String,
+    pub register_writes: Vec<RegisterWrite>,
+    pub memory: Vec<TraceAccess>,
+    /// Gas remaining after the instruction, if metered
+    pub gas: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Trace {
+    pub entries: Vec<TraceEntry>,
+    /// Set once `MAX_TRACE_ENTRIES` is reached and recording stopped
+    pub truncated: bool,
+}
+
+impl Trace {
+    pub fn push(&mut self, entry: TraceEntry) {
+        if self.entries.len() < MAX_TRACE_ENTRIES {
+            self.entries.push(entry);
+        } else {
+            self.truncated = true;
+        }
+    }
+
+    /// The trace as it was after `step` instructions.
+    pub fn rewound_to(&self, step: u64) -> Self {
+        Trace {
+            entries: self.entries.iter().take_while(|entry| entry.step <= step).cloned().collect(),
+            truncated: self.truncated && self.entries.last().map_or(false, |entry| entry.step <= step),
+        }
+    }
+
+    pub fn to_jsonl(&self) -> String {
+        self.entries
+            .iter()
+            .filter_map(|entry| serde_json::to_string(entry).ok())
+            .map(|line| line + "\n")
+            .collect()
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(CSV_HEADER);
+        csv.push('\n');
+        for entry in &self.entries {
+            let register_writes = entry
+                .register_writes
+                .iter()
+                .map(|write| format!("{}=0x{:x}", write.reg, write.value))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let memory = entry
+                .memory
+                .iter()
+                .map(|access| format!("{}:0x{:x}:{}", if access.write { "w" } else { "r" }, access.address, access.length))
+                .collect::<Vec<_>>()
+                .join(" ");
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{},{}\n",
+                entry.step,
+                entry.pc,
+                entry.offset,
+                entry.opcode,
+                quote_csv(&entry.assembly),
+                register_writes,
+                memory,
+                entry.gas.map(|gas| gas.to_string()).unwrap_or_default()
+            ));
+        }
+        csv
+    }
+
+    /// Reads back a trace written by `to_jsonl` or `to_csv`, picking the
+    /// format from the file name.
+    pub fn parse(filename: &str, contents: &str) -> Result<Self, String> {
+        let entries = if filename.ends_with(".csv") {
+            parse_csv(contents)?
+        } else {
+            contents
+                .lines()
+                .enumerate()
+                .filter(|(_, line)| !line.trim().is_empty())
+                .map(|(number, line)| {
+                    serde_json::from_str(line).map_err(|error| format!("Line {}: {}", number + 1, error))
+                })
+                .collect::<Result<Vec<_>, _>>()?
+        };
+        Ok(Trace {
+            entries,
+            truncated: false,
+        })
+    }
+}
+
+fn quote_csv(field: &str) -> String {
+    format!("\"{}\"", field.replace('"', "\"\""))
+}
+
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(character) = chars.next() {
+        match character {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(character),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+fn parse_hex(text: &str) -> Option<u32> {
+    u32::from_str_radix(text.strip_prefix("0x")?, 16).ok()
+}
+
+fn parse_csv(contents: &str) -> Result<Vec<TraceEntry>, String> {
+    let mut lines = contents.lines().enumerate();
+    match lines.next() {
+        Some((_, header)) if header.trim() == CSV_HEADER => {}
+        _ => return Err(format!("Expected CSV header '{}'", CSV_HEADER)),
+    }
+
+    lines
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(number, line)| {
+            let error = |what: &str| format!("Line {}: invalid {}", number + 1, what);
+            let fields = split_csv_line(line);
+            let [step, pc, offset, opcode, assembly, register_writes, memory, gas] = fields.as_slice() else {
+                return Err(error("field count"));
+            };
+
+            let register_writes = register_writes
+                .split_whitespace()
+                .map(|write| {
+                    let (reg, value) = write.split_once('=')?;
+                    Some(RegisterWrite {
+                        reg: reg.to_string(),
+                        value: parse_hex(value)?,
+                    })
+                })
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| error("register writes"))?;
+            let memory = memory
+                .split_whitespace()
+                .map(|access| {
+                    let mut parts = access.split(':');
+                    let write = match parts.next()? {
+                        "w" => true,
+                        "r" => false,
+                        _ => return None,
+                    };
+                    Some(TraceAccess {
+                        write,
+                        address: parse_hex(parts.next()?)?,
+                        length: parts.next()?.parse().ok()?,
+                    })
+                })
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| error("memory accesses"))?;
+
+            Ok(TraceEntry {
+                step: step.parse().map_err(|_| error("step"))?,
+                pc: pc.parse().map_err(|_| error("pc"))?,
+                offset: offset.parse().map_err(|_| error("offset"))?,
+                opcode: opcode.clone(),
+                assembly: assembly.clone(),
+                register_writes,
+                memory,
+                gas: if gas.is_empty() {
+                    None
+                } else {
+                    Some(gas.parse().map_err(|_| error("gas"))?)
+                },
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trace() -> Trace {
+        let mut trace = Trace::default();
+        trace.push(TraceEntry {
+            step: 1,
+            pc: 0,
+            offset: 0x2a,
+            opcode: "store_u32".to_string(),
+            assembly: "u32 [sp + 4] = a0, \"quoted\"".to_string(),
+            register_writes: Vec::new(),
+            memory: vec![
+                TraceAccess { address: 0xfffdfff0, length: 4, write: true },
+                TraceAccess { address: 0x20000, length: 1, write: false },
+            ],
+            gas: Some(99),
+        });
+        trace.push(TraceEntry {
+            step: 2,
+            pc: 1,
+            offset: 0x2e,
+            opcode: "add".to_string(),
+            assembly: "a2 = a0 + a1".to_string(),
+            register_writes: vec![
+                RegisterWrite { reg: "a2".to_string(), value: 0xdeadbeef },
+                RegisterWrite { reg: "sp".to_string(), value: 0 },
+            ],
+            memory: Vec::new(),
+            gas: None,
+        });
+        trace
+    }
+
+    #[test]
+    fn jsonl_round_trips() {
+        let trace = trace();
+        assert_eq!(Trace::parse("run.trace.jsonl", &trace.to_jsonl()), Ok(trace));
+    }
+
+    #[test]
+    fn csv_round_trips() {
+        let trace = trace();
+        let csv = trace.to_csv();
+        assert!(csv.contains(",w:0xfffdfff0:4 r:0x20000:1,99\n"));
+        assert_eq!(Trace::parse("run.trace.csv", &csv), Ok(trace));
+    }
+
+    #[test]
+    fn malformed_lines_are_reported_by_number() {
+        let csv = format!("{}\n1,0,42,add,\"a2 = a0 + a1\",a2=0x1,x:0x10:4,\n", CSV_HEADER);
+        assert_eq!(Trace::parse("run.trace.csv", &csv), Err("Line 2: invalid memory accesses".to_string()));
+
+        let csv = format!("{}\n1,0,42,add\n", CSV_HEADER);
+        assert_eq!(Trace::parse("run.trace.csv", &csv), Err("Line 2: invalid field count".to_string()));
+
+        assert!(Trace::parse("run.trace.csv", "step,pc\n").is_err());
+        assert!(Trace::parse("run.trace.jsonl", "{}\n").unwrap_err().starts_with("Line 1: "));
+    }
+}
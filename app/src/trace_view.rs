@@ -0,0 +1,198 @@
+use crate::file_upload::{download_file, FileUploadComponent};
+use crate::interpreter::Interpreter;
+use crate::trace::{Trace, TraceEntry};
+use leptos::*;
+
+const ROWS_PER_PAGE: usize = 100;
+
+fn describe_writes(entry: &TraceEntry) -> String {
+    entry
+        .register_writes
+        .iter()
+        .map(|write| format!("{}=0x{:x}", write.reg, write.value))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn describe_memory(entry: &TraceEntry) -> String {
+    entry
+        .memory
+        .iter()
+        .map(|access| {
+            let kind = if access.write { "w" } else { "r" };
+            format!("{} 0x{:08x}+{}", kind, access.address, access.length)
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Paged table over the live or an imported trace; selecting a row reports
+/// the instruction index so the listing can follow.
+#[component]
+pub fn TraceView<F: Fn(usize) + Copy + 'static>(
+    interpreter: RwSignal<Option<Interpreter>>,
+    tracing: RwSignal<bool>,
+    filename: ReadSignal<String>,
+    on_entry_selected: F,
+) -> impl IntoView {
+    let imported = create_rw_signal(None::<(String, Trace)>);
+    let (import_error, set_import_error) = create_signal(None::<String>);
+    // `None` follows the end of the trace as it grows
+    let (page, set_page) = create_signal(None::<usize>);
+    let (selected_step, set_selected_step) = create_signal(None::<u64>);
+
+    // Runs `f` against whichever trace is on display
+    let with_trace = move |f: &dyn Fn(&Trace) -> Option<View>| -> Option<View> {
+        if let Some(view) = imported.with(|imported| imported.as_ref().map(|(_, trace)| f(trace))) {
+            return view;
+        }
+        interpreter.with(|vm| vm.as_ref().and_then(|vm| vm.trace()).and_then(f))
+    };
+    let entry_count = move || {
+        imported.with(|imported| imported.as_ref().map(|(_, trace)| trace.entries.len())).unwrap_or_else(|| {
+            interpreter.with(|vm| vm.as_ref().and_then(|vm| vm.trace()).map_or(0, |trace| trace.entries.len()))
+        })
+    };
+    let has_trace = move || interpreter.with(|vm| vm.as_ref().map_or(false, |vm| vm.trace().is_some()));
+    let page_count = move || entry_count().div_ceil(ROWS_PER_PAGE).max(1);
+    let current_page = move || page().unwrap_or_else(|| page_count() - 1).min(page_count() - 1);
+
+    let export = move |csv: bool| {
+        interpreter.with_untracked(|vm| {
+            let Some(trace) = vm.as_ref().and_then(|vm| vm.trace()) else {
+                return;
+            };
+            let name = filename.get_untracked();
+            if csv {
+                download_file(&format!("{}.trace.csv", name), "text/csv", &trace.to_csv());
+            } else {
+                download_file(&format!("{}.trace.jsonl", name), "application/jsonl", &trace.to_jsonl());
+            }
+        });
+    };
+
+    let button_class = "px-2 py-1 text-xs font-semibold text-gray-700 bg-white hover:bg-gray-100 rounded-xs border border-gray-300 disabled:opacity-50";
+
+    view! {
+        <div class="w-full px-4 pb-4">
+            <div class="h-50vh overflow-auto border border-gray-200 rounded">
+                <div class="flex flex-wrap items-center gap-2 p-2 bg-gray-200 text-sm">
+                    <span class="font-bold">"Trace"</span>
+                    <label class="flex items-center gap-1">
+                        <input
+                            type="checkbox"
+                            prop:checked=tracing
+                            on:change=move |event| tracing.set(event_target_checked(&event))
+                        />
+                        "Record"
+                    </label>
+                    <button class=button_class disabled=move || !has_trace() on:click=move |_| export(false)>"Export JSONL"</button>
+                    <button class=button_class disabled=move || !has_trace() on:click=move |_| export(true)>"Export CSV"</button>
+                    <button
+                        class=button_class
+                        disabled=move || current_page() == 0
+                        on:click=move |_| set_page(Some(current_page().saturating_sub(1)))
+                    >
+                        "Previous"
+                    </button>
+                    <span class="font-mono text-xs">
+                        {move || format!("page {} of {}, {} entries", current_page() + 1, page_count(), entry_count())}
+                    </span>
+                    <button
+                        class=button_class
+                        disabled=move || current_page() + 1 >= page_count()
+                        on:click=move |_| set_page(Some(current_page() + 1))
+                    >
+                        "Next"
+                    </button>
+                    <button class=button_class on:click=move |_| set_page(None)>"Follow"</button>
+                    {move || {
+                        imported
+                            .with(|imported| imported.as_ref().map(|(name, _)| name.clone()))
+                            .map(|name| {
+                                view! {
+                                    <span class="text-xs">{format!("Viewing imported {}", name)}</span>
+                                    <button class=button_class on:click=move |_| imported.set(None)>"Back to live trace"</button>
+                                }
+                            })
+                    }}
+                    {move || {
+                        interpreter
+                            .with(|vm| vm.as_ref().and_then(|vm| vm.trace()).map_or(false, |trace| trace.truncated))
+                            .then(|| view! { <span class="text-xs text-red-600">"Trace limit reached, recording stopped"</span> })
+                    }}
+                    <div class="text-xs border-dashed border-2 border-gray-400 px-2">
+                        <FileUploadComponent
+                            accept=".jsonl,.csv"
+                            on_file_uploaded=move |data_option, name| {
+                                let Some(data) = data_option else {
+                                    return;
+                                };
+                                let parsed = String::from_utf8(data)
+                                    .map_err(|_| "Trace is not valid UTF-8".to_string())
+                                    .and_then(|contents| Trace::parse(&name, &contents));
+                                match parsed {
+                                    Ok(trace) => {
+                                        set_import_error(None);
+                                        set_page(Some(0));
+                                        imported.set(Some((name, trace)));
+                                    }
+                                    Err(message) => set_import_error(Some(message)),
+                                }
+                            }
+                        />
+                    </div>
+                    {move || import_error().map(|message| view! { <span class="text-xs text-red-600">{message}</span> })}
+                </div>
+                <table class="w-full text-xs font-mono">
+                    <thead>
+                        <tr class="text-left bg-gray-100">
+                            <th class="p-1">"Step"</th>
+                            <th class="p-1">"Offset"</th>
+                            <th class="p-1">"Instruction"</th>
+                            <th class="p-1">"Registers"</th>
+                            <th class="p-1">"Memory"</th>
+                            <th class="p-1">"Gas"</th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        {move || {
+                            let start = current_page() * ROWS_PER_PAGE;
+                            let selected = selected_step();
+                            with_trace(&|trace| {
+                                let rows = trace
+                                    .entries
+                                    .iter()
+                                    .skip(start)
+                                    .take(ROWS_PER_PAGE)
+                                    .map(|entry| {
+                                        let pc = entry.pc;
+                                        let step = entry.step;
+                                        view! {
+                                            <tr
+                                                class="border-t border-gray-200 cursor-pointer hover:bg-gray-100"
+                                                class:bg-yellow-100=selected == Some(step)
+                                                on:click=move |_| {
+                                                    set_selected_step(Some(step));
+                                                    on_entry_selected(pc);
+                                                }
+                                            >
+                                                <td class="p-1">{step.to_string()}</td>
+                                                <td class="p-1">{format!("{:06X}", entry.offset)}</td>
+                                                <td class="p-1">{entry.assembly.clone()}</td>
+                                                <td class="p-1">{describe_writes(entry)}</td>
+                                                <td class="p-1">{describe_memory(entry)}</td>
+                                                <td class="p-1">{entry.gas.map(|gas| gas.to_string()).unwrap_or_default()}</td>
+                                            </tr>
+                                        }
+                                    })
+                                    .collect::<Vec<_>>();
+                                Some(rows.into_view())
+                            })
+                        }}
+                    </tbody>
+                </table>
+            </div>
+        </div>
+    }
+}
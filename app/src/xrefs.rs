@@ -0,0 +1,127 @@
+use crate::analysis::{ProgramAnalysis, XRef, XRefKind};
+use crate::settings::use_settings;
+use polkarun_core::listing::ListingFormat;
+use leptos::*;
+use std::rc::Rc;
+
+fn xref_kind_label(kind: XRefKind) -> String {
+    match kind {
+        XRefKind::Branch => "branch".to_string(),
+        XRefKind::Jump => "jump".to_string(),
+        XRefKind::Call => "call".to_string(),
+        XRefKind::JumpTable(entry) => format!("jump table [{}]", entry),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct XRefRow {
+    xref: XRef,
+    kind: String,
+    location: String,
+    function: String,
+    assembly: String,
+}
+
+fn xref_rows(analysis: &ProgramAnalysis, jump_target: u32, format: &ListingFormat) -> Vec<XRefRow> {
+    analysis
+        .xrefs_to(jump_target)
+        .iter()
+        .map(|&xref| {
+            let site = xref.instruction.and_then(|index| Some((index, analysis.instructions.get(index)?)));
+            let (location, function, assembly) = match site {
+                Some((index, analyzed)) => {
+                    let function = analysis
+                        .block_of_instruction(index)
+                        .and_then(|block| analysis.function_of_block(block.jump_target))
+                        .map_or_else(String::new, |function| function.name.clone());
+                    (
+                        format.offset(analysis.code_offset + analyzed.offset),
+                        function,
+                        format.assembly(&analyzed.instruction.to_string()),
+                    )
+                }
+                None => (String::from("-"), String::new(), String::new()),
+            };
+
+            XRefRow {
+                xref,
+                kind: xref_kind_label(xref.kind),
+                location,
+                function,
+                assembly,
+            }
+        })
+        .collect()
+}
+
+#[component]
+pub fn XRefPanel<F: Fn(usize) + 'static, C: Fn() + 'static>(
+    analysis: ReadSignal<ProgramAnalysis>,
+    target: ReadSignal<Option<u32>>,
+    on_xref_selected: F,
+    on_close: C,
+) -> impl IntoView {
+    let on_xref_selected = Rc::new(on_xref_selected);
+    let settings = use_settings();
+    let rows = create_memo(move |_| {
+        let format = settings().format;
+        target()
+            .map(|jump_target| analysis.with(|analysis| xref_rows(analysis, jump_target, &format)))
+            .unwrap_or_default()
+    });
+
+    view! {
+        <div class="mt-4 p-2 bg-gray-100 w-full shadow-md text-xs dark:bg-gray-800">
+            <div class="flex justify-between font-bold pb-1">
+                <span>{move || target().map(|jump_target| format!("References to @{}", jump_target))}</span>
+                <span class="cursor-pointer" on:click=move |_| on_close()>
+                    <div class="i-mdi-close" />
+                </span>
+            </div>
+            <Show
+                when=move || !rows.with(Vec::is_empty)
+                fallback=|| view! { <div class="text-gray-500">"No references"</div> }
+            >
+                <table class="w-full table-fixed font-mono">
+                    <thead>
+                        <tr class="text-left bg-gray-200 dark:bg-gray-700">
+                            <th class="p-1 w-14">"Offset"</th>
+                            <th class="p-1">"From"</th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        {
+                            let on_xref_selected = on_xref_selected.clone();
+                            move || {
+                                rows()
+                                    .into_iter()
+                                    .map(|row| {
+                                        let on_xref_selected = on_xref_selected.clone();
+                                        let instruction = row.xref.instruction;
+                                        view! {
+                                            <tr
+                                                class="hover:bg-gray-200 dark:hover:bg-gray-600"
+                                                class:cursor-pointer=instruction.is_some()
+                                                title=row.assembly.clone()
+                                                on:click=move |_| {
+                                                    if let Some(index) = instruction {
+                                                        on_xref_selected(index);
+                                                    }
+                                                }
+                                            >
+                                                <td class="p-1">{row.location.clone()}</td>
+                                                <td class="p-1 truncate">
+                                                    {format!("{} {}", row.kind, row.function)}
+                                                </td>
+                                            </tr>
+                                        }
+                                    })
+                                    .collect::<Vec<_>>()
+                            }
+                        }
+                    </tbody>
+                </table>
+            </Show>
+        </div>
+    }
+}
@@ -0,0 +1,321 @@
+use clap::{Parser, Subcommand, ValueEnum};
+use polkarun_core::analysis::ProgramAnalysis;
+use polkarun_core::listing::{disassemble_into_lines, unified_representation, DisassembledLine, ListingFormat};
+use polkarun_core::sections::{blob_sections, BlobSection};
+use polkarun_core::size::{compare_sizes, size_report, Budget, BudgetViolation, SizeDelta, SizeReport};
+use polkarun_core::symbols::{program_symbols, ProgramSymbols};
+use polkavm_common::program::ProgramBlob;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+/// Inspects PolkaVM program blobs the same way polka.run does.
+#[derive(Parser)]
+#[command(name = "polkarun", version)]
+struct Cli {
+    /// Output format
+    #[arg(long, value_enum, default_value_t = Format::Text, global = true)]
+    format: Format,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Disassembly listing grouped into functions and basic blocks
+    Disassemble { file: PathBuf },
+    /// Hex dump of the whole blob
+    Hex {
+        file: PathBuf,
+        /// Bytes per row
+        #[arg(long, default_value_t = 16, value_parser = clap::value_parser!(u8).range(1..))]
+        row_size: u8,
+    },
+    /// Sections of the blob and what they contain
+    Sections { file: PathBuf },
+    /// Exported and imported symbols
+    Symbols { file: PathBuf },
+    /// Size breakdown by section, function and export. Exits with status 2
+    /// when a budget is exceeded.
+    Size {
+        file: PathBuf,
+        /// Blob to compare against
+        #[arg(long)]
+        baseline: Option<PathBuf>,
+        /// Budget file, TOML or RON
+        #[arg(long)]
+        budget: Option<PathBuf>,
+        /// Largest allowed total size in bytes, overriding the budget file
+        #[arg(long)]
+        max_total: Option<usize>,
+        /// Largest allowed growth over the baseline in bytes, overriding the
+        /// budget file
+        #[arg(long, requires = "baseline")]
+        max_growth: Option<i64>,
+    },
+}
+
+// Status for a budget violation, apart from errors
+const BUDGET_EXCEEDED: u8 = 2;
+
+fn read_blob(path: &Path) -> Result<Vec<u8>, String> {
+    std::fs::read(path).map_err(|error| format!("Failed to read {}: {}", path.display(), error))
+}
+
+fn print_json<T: Serialize>(value: &T) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(value).map_err(|error| format!("Failed to serialize: {}", error))?;
+    println!("{}", json);
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct FunctionEntry<'a> {
+    name: &'a str,
+    entry: u32,
+    blocks: Vec<u32>,
+}
+
+#[derive(Serialize)]
+struct Listing<'a> {
+    functions: Vec<FunctionEntry<'a>>,
+    lines: &'a [DisassembledLine],
+}
+
+fn print_line(line: &DisassembledLine, hex_width: usize) {
+    let comment = line.comment.as_ref().map(|comment| format!("  ; {}", comment)).unwrap_or_default();
+    println!("  {}  {:<hex_width$}  {}{}", line.offset, line.hex, line.assembly, comment);
+}
+
+// Same grouping as the Disassembler page: function headers, then each block
+// with its instructions
+fn print_listing(lines: &[DisassembledLine], analysis: &ProgramAnalysis) {
+    let hex_width = lines.iter().map(|line| line.hex.len()).max().unwrap_or(0);
+    for block in &analysis.blocks {
+        let jump_target = block.jump_target;
+        if let Some(function) = analysis
+            .function_of_block(jump_target)
+            .filter(|function| function.entry == jump_target)
+        {
+            println!();
+            println!("fn {}", function.name);
+        }
+        println!("@{}: {} instructions", jump_target, block.instructions.len());
+        for line in lines.get(block.instructions.clone()).unwrap_or_default() {
+            print_line(line, hex_width);
+        }
+    }
+
+    // Lines past the analyzed code, e.g. a parse error
+    let analyzed_count = analysis.blocks.last().map_or(0, |block| block.instructions.end);
+    for line in lines.iter().skip(analyzed_count) {
+        print_line(line, hex_width);
+    }
+}
+
+fn disassemble(path: &Path, format: Format) -> Result<(), String> {
+    let data = read_blob(path)?;
+    let (lines, analysis) = disassemble_into_lines(&data)?;
+    match format {
+        Format::Text => print_listing(&lines, &analysis),
+        Format::Json => print_json(&Listing {
+            functions: analysis
+                .functions
+                .iter()
+                .map(|function| FunctionEntry {
+                    name: &function.name,
+                    entry: function.entry,
+                    blocks: function.blocks.iter().copied().collect(),
+                })
+                .collect(),
+            lines: &lines,
+        })?,
+    }
+    Ok(())
+}
+
+fn hex(path: &Path, row_size: u8, format: Format) -> Result<(), String> {
+    let data = read_blob(path)?;
+    let listing_format = ListingFormat {
+        bytes_per_row: row_size as usize,
+        ..ListingFormat::default()
+    };
+    let rows = unified_representation(&data, &listing_format);
+    match format {
+        Format::Text => rows.iter().for_each(|row| println!("{}", row)),
+        Format::Json => print_json(&rows)?,
+    }
+    Ok(())
+}
+
+fn print_sections(sections: &[BlobSection]) {
+    for section in sections {
+        println!(
+            "{:<28} {:06x}..{:06x} ({} bytes)",
+            section.name,
+            section.range.start,
+            section.range.end,
+            section.size()
+        );
+        for line in &section.contents {
+            println!("    {}", line);
+        }
+    }
+}
+
+fn sections(path: &Path, format: Format) -> Result<(), String> {
+    let data = read_blob(path)?;
+    let sections = blob_sections(&data)?;
+    match format {
+        Format::Text => print_sections(&sections),
+        Format::Json => print_json(&sections)?,
+    }
+    Ok(())
+}
+
+fn print_symbols(symbols: &ProgramSymbols) {
+    println!("Exports");
+    for export in &symbols.exports {
+        println!("    {} @ {}", export.name, export.jump_target);
+    }
+    println!("Imports");
+    for import in &symbols.imports {
+        println!("    #{}: {}", import.index, import.name);
+    }
+}
+
+fn symbols(path: &Path, format: Format) -> Result<(), String> {
+    let data = read_blob(path)?;
+    let blob = ProgramBlob::parse(&data[..]).map_err(|_| "Failed to parse blob")?;
+    let symbols = program_symbols(&blob)?;
+    match format {
+        Format::Text => print_symbols(&symbols),
+        Format::Json => print_json(&symbols)?,
+    }
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct SizeOutput<'a> {
+    report: &'a SizeReport,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    changes: Option<&'a [SizeDelta]>,
+    violations: &'a [BudgetViolation],
+}
+
+fn print_size_report(report: &SizeReport) {
+    let share = |size: usize| size as f64 * 100.0 / report.total.max(1) as f64;
+    println!("{:>10}  {:>6}  Section", "Bytes", "%");
+    for section in &report.sections {
+        println!("{:>10}  {:>5.1}%  {}", section.size, share(section.size), section.name);
+    }
+    println!("{:>10}  {:>5.1}%  Total", report.total, 100.0);
+
+    println!();
+    println!("{:>10}  {:>6}  Function", "Bytes", "%");
+    for function in &report.functions {
+        println!("{:>10}  {:>5.1}%  {}", function.size, share(function.size), function.name);
+    }
+
+    println!();
+    println!("{:>10}  {:>10}  Export", "Bytes", "Retained");
+    for export in &report.exports {
+        println!("{:>10}  {:>10}  {}", export.size, export.retained, export.name);
+    }
+}
+
+fn print_size_changes(changes: &[SizeDelta]) {
+    let show = |size: Option<usize>| size.map_or_else(|| "-".to_string(), |size| size.to_string());
+    println!();
+    println!("{:>10}  {:>10}  {:>8}  Change", "Baseline", "Current", "Delta");
+    for change in changes {
+        println!(
+            "{:>10}  {:>10}  {:>+8}  {:?} {}",
+            show(change.baseline),
+            show(change.current),
+            change.delta(),
+            change.category,
+            change.name
+        );
+    }
+}
+
+fn size(
+    path: &Path,
+    baseline: Option<&Path>,
+    budget: Option<&Path>,
+    max_total: Option<usize>,
+    max_growth: Option<i64>,
+    format: Format,
+) -> Result<bool, String> {
+    let report = size_report(&read_blob(path)?)?;
+    let baseline = match baseline {
+        Some(baseline) => Some(size_report(&read_blob(baseline)?)?),
+        None => None,
+    };
+    let changes = baseline.as_ref().map(|baseline| compare_sizes(baseline, &report));
+
+    let mut budget = match budget {
+        Some(budget) => {
+            let contents = std::fs::read_to_string(budget)
+                .map_err(|error| format!("Failed to read {}: {}", budget.display(), error))?;
+            Budget::parse(&budget.to_string_lossy(), &contents)?
+        }
+        None => Budget::default(),
+    };
+    budget.total = max_total.or(budget.total);
+    budget.growth = max_growth.or(budget.growth);
+    let violations = budget.check(&report, baseline.as_ref())?;
+
+    match format {
+        Format::Text => {
+            print_size_report(&report);
+            if let Some(changes) = &changes {
+                print_size_changes(changes);
+            }
+            for violation in &violations {
+                eprintln!(
+                    "budget exceeded: {} is {} bytes, limit {}",
+                    violation.what, violation.actual, violation.limit
+                );
+            }
+        }
+        Format::Json => print_json(&SizeOutput {
+            report: &report,
+            changes: changes.as_deref(),
+            violations: &violations,
+        })?,
+    }
+    Ok(violations.is_empty())
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let result = match &cli.command {
+        Command::Disassemble { file } => disassemble(file, cli.format).map(|()| true),
+        Command::Hex { file, row_size } => hex(file, *row_size, cli.format).map(|()| true),
+        Command::Sections { file } => sections(file, cli.format).map(|()| true),
+        Command::Symbols { file } => symbols(file, cli.format).map(|()| true),
+        Command::Size {
+            file,
+            baseline,
+            budget,
+            max_total,
+            max_growth,
+        } => size(file, baseline.as_deref(), budget.as_deref(), *max_total, *max_growth, cli.format),
+    };
+
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(BUDGET_EXCEEDED),
+        Err(error) => {
+            eprintln!("error: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
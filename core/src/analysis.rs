@@ -0,0 +1,404 @@
+use crate::sections::subslice_range;
+use polkavm_common::program::{Instruction, ProgramBlob, Reg};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// How an instruction hands over control once it has executed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ControlFlow {
+    /// Execution continues with the next instruction of the same block
+    Continue,
+    /// Block ends and execution continues with the next block
+    Fallthrough,
+    Jump(u32),
+    /// Conditional: either the target or the next block
+    Branch(u32),
+    /// Calls into the target, returning to the next block
+    Call(u32),
+    IndirectCall,
+    IndirectJump,
+    Return,
+    Trap,
+}
+
+pub fn control_flow(instruction: &Instruction) -> ControlFlow {
+    use Instruction as I;
+    match *instruction {
+        I::trap => ControlFlow::Trap,
+        I::fallthrough => ControlFlow::Fallthrough,
+        I::jump(target) => ControlFlow::Jump(target),
+        I::call(_, target) => ControlFlow::Call(target),
+        I::call_indirect(..) => ControlFlow::IndirectCall,
+        I::jump_indirect(Reg::RA, 0) => ControlFlow::Return,
+        I::jump_indirect(..) => ControlFlow::IndirectJump,
+        I::branch_eq(_, _, target)
+        | I::branch_not_eq(_, _, target)
+        | I::branch_less_unsigned(_, _, target)
+        | I::branch_less_signed(_, _, target)
+        | I::branch_greater_or_equal_unsigned(_, _, target)
+        | I::branch_greater_or_equal_signed(_, _, target)
+        | I::branch_eq_imm(_, _, target)
+        | I::branch_not_eq_imm(_, _, target)
+        | I::branch_less_unsigned_imm(_, _, target)
+        | I::branch_less_signed_imm(_, _, target)
+        | I::branch_greater_or_equal_unsigned_imm(_, _, target)
+        | I::branch_greater_or_equal_signed_imm(_, _, target)
+        | I::branch_less_or_equal_unsigned_imm(_, _, target)
+        | I::branch_less_or_equal_signed_imm(_, _, target)
+        | I::branch_greater_unsigned_imm(_, _, target)
+        | I::branch_greater_signed_imm(_, _, target) => ControlFlow::Branch(target),
+        _ if instruction.opcode().starts_new_basic_block() => ControlFlow::Fallthrough,
+        _ => ControlFlow::Continue,
+    }
+}
+
+/// Opcode mnemonic such as `add` or `branch_eq_imm`.
+pub fn opcode_name(instruction: &Instruction) -> String {
+    format!("{:?}", instruction.opcode())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AnalyzedInstruction {
+    /// Offset within the code section
+    pub offset: usize,
+    pub length: usize,
+    pub instruction: Instruction,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EdgeKind {
+    Taken,
+    NotTaken,
+    Jump,
+    Fallthrough,
+    /// From a call site to the block the callee returns to
+    CallReturn,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Edge {
+    pub target: u32,
+    pub kind: EdgeKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BasicBlock {
+    pub jump_target: u32,
+    /// Range of instruction indices covered by the block
+    pub instructions: std::ops::Range<usize>,
+    pub successors: Vec<Edge>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum XRefKind {
+    Branch,
+    Jump,
+    Call,
+    /// Entry of the jump table used by indirect jumps and calls
+    JumpTable(usize),
+}
+
+/// A site that transfers control to a basic block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct XRef {
+    pub kind: XRefKind,
+    /// Instruction index of the referencing instruction, if it is one
+    pub instruction: Option<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Function {
+    pub name: String,
+    pub entry: u32,
+    pub blocks: BTreeSet<u32>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProgramAnalysis {
+    /// Offset of the code section within the blob
+    pub code_offset: usize,
+    pub instructions: Vec<AnalyzedInstruction>,
+    pub blocks: Vec<BasicBlock>,
+    pub functions: Vec<Function>,
+    /// Which function each block was assigned to, by index into `functions`
+    pub block_function: HashMap<u32, usize>,
+    /// Every reference to a jump target, keyed by the target
+    pub xrefs: BTreeMap<u32, Vec<XRef>>,
+}
+
+impl ProgramAnalysis {
+    pub fn block(&self, jump_target: u32) -> Option<&BasicBlock> {
+        self.blocks.get(jump_target as usize)
+    }
+
+    pub fn block_of_instruction(&self, index: usize) -> Option<&BasicBlock> {
+        let position = self
+            .blocks
+            .partition_point(|block| block.instructions.end <= index);
+        self.blocks
+            .get(position)
+            .filter(|block| block.instructions.contains(&index))
+    }
+
+    /// Bytes of the blob occupied by the block's instructions
+    pub fn block_byte_range(&self, jump_target: u32) -> Option<std::ops::Range<usize>> {
+        let block = self.block(jump_target)?;
+        let first = self.instructions.get(block.instructions.start)?;
+        let last = self.instructions.get(block.instructions.end - 1)?;
+        Some(self.code_offset + first.offset..self.code_offset + last.offset + last.length)
+    }
+
+    pub fn xrefs_to(&self, jump_target: u32) -> &[XRef] {
+        self.xrefs.get(&jump_target).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn function_of_block(&self, jump_target: u32) -> Option<&Function> {
+        self.block_function
+            .get(&jump_target)
+            .map(|&index| &self.functions[index])
+    }
+}
+
+fn split_into_blocks(instructions: &[AnalyzedInstruction]) -> Vec<BasicBlock> {
+    let mut blocks = Vec::new();
+    let mut block_start = 0;
+
+    for (index, analyzed) in instructions.iter().enumerate() {
+        let flow = control_flow(&analyzed.instruction);
+        if flow == ControlFlow::Continue && index + 1 != instructions.len() {
+            continue;
+        }
+
+        let jump_target = blocks.len() as u32;
+        let next = jump_target + 1;
+        let successors = match flow {
+            ControlFlow::Continue | ControlFlow::Fallthrough => vec![Edge { target: next, kind: EdgeKind::Fallthrough }],
+            ControlFlow::Jump(target) => vec![Edge { target, kind: EdgeKind::Jump }],
+            ControlFlow::Branch(target) => vec![
+                Edge { target, kind: EdgeKind::Taken },
+                Edge { target: next, kind: EdgeKind::NotTaken },
+            ],
+            ControlFlow::Call(_) | ControlFlow::IndirectCall => vec![Edge { target: next, kind: EdgeKind::CallReturn }],
+            ControlFlow::IndirectJump | ControlFlow::Return | ControlFlow::Trap => Vec::new(),
+        };
+
+        blocks.push(BasicBlock {
+            jump_target,
+            instructions: block_start..index + 1,
+            successors,
+        });
+        block_start = index + 1;
+    }
+
+    // Edges past the last block (e.g. a trailing fallthrough) lead nowhere
+    let block_count = blocks.len() as u32;
+    for block in &mut blocks {
+        block.successors.retain(|edge| edge.target < block_count);
+    }
+
+    blocks
+}
+
+fn find_functions(
+    blocks: &[BasicBlock],
+    instructions: &[AnalyzedInstruction],
+    exports: &[(String, u32)],
+) -> (Vec<Function>, HashMap<u32, usize>) {
+    // Function entry points: every call target, named after the export
+    // where there is one
+    let mut entries = BTreeMap::<u32, String>::new();
+    for block in blocks {
+        let last = &instructions[block.instructions.end - 1];
+        if let ControlFlow::Call(target) = control_flow(&last.instruction) {
+            entries.entry(target).or_insert_with(|| format!("sub_{}", target));
+        }
+    }
+    for (name, target) in exports {
+        entries.insert(*target, name.clone());
+    }
+
+    let mut functions = Vec::new();
+    let mut block_function = HashMap::new();
+    let claim = |entry: u32, name: String, functions: &mut Vec<Function>, block_function: &mut HashMap<u32, usize>| {
+        let function_index = functions.len();
+        let mut function_blocks = BTreeSet::new();
+        let mut pending = vec![entry];
+        while let Some(jump_target) = pending.pop() {
+            let Some(block) = blocks.get(jump_target as usize) else {
+                continue;
+            };
+            // Entries of other functions bound this one; tail jumps into them
+            // don't make their blocks ours
+            if jump_target != entry && (entries.contains_key(&jump_target) || block_function.contains_key(&jump_target)) {
+                continue;
+            }
+            if !function_blocks.insert(jump_target) {
+                continue;
+            }
+            block_function.insert(jump_target, function_index);
+            pending.extend(block.successors.iter().map(|edge| edge.target));
+        }
+
+        functions.push(Function {
+            name,
+            entry,
+            blocks: function_blocks,
+        });
+    };
+
+    for (&entry, name) in &entries {
+        if !block_function.contains_key(&entry) {
+            claim(entry, name.clone(), &mut functions, &mut block_function);
+        }
+    }
+
+    // Code only reachable through the jump table or not at all
+    for block in blocks {
+        if !block_function.contains_key(&block.jump_target) {
+            claim(block.jump_target, format!("sub_{}", block.jump_target), &mut functions, &mut block_function);
+        }
+    }
+
+    (functions, block_function)
+}
+
+fn find_xrefs(instructions: &[AnalyzedInstruction], jump_table: &[u32]) -> BTreeMap<u32, Vec<XRef>> {
+    let mut xrefs = BTreeMap::<u32, Vec<XRef>>::new();
+    for (index, analyzed) in instructions.iter().enumerate() {
+        let (target, kind) = match control_flow(&analyzed.instruction) {
+            ControlFlow::Branch(target) => (target, XRefKind::Branch),
+            ControlFlow::Jump(target) => (target, XRefKind::Jump),
+            ControlFlow::Call(target) => (target, XRefKind::Call),
+            _ => continue,
+        };
+        xrefs.entry(target).or_default().push(XRef {
+            kind,
+            instruction: Some(index),
+        });
+    }
+
+    for (entry, &target) in jump_table.iter().enumerate() {
+        xrefs.entry(target).or_default().push(XRef {
+            kind: XRefKind::JumpTable(entry),
+            instruction: None,
+        });
+    }
+
+    xrefs
+}
+
+pub fn analyze_instructions(
+    instructions: Vec<AnalyzedInstruction>,
+    exports: &[(String, u32)],
+    jump_table: &[u32],
+) -> ProgramAnalysis {
+    let blocks = split_into_blocks(&instructions);
+    let (functions, block_function) = find_functions(&blocks, &instructions, exports);
+    let xrefs = find_xrefs(&instructions, jump_table);
+
+    ProgramAnalysis {
+        code_offset: 0,
+        instructions,
+        blocks,
+        functions,
+        block_function,
+        xrefs,
+    }
+}
+
+pub fn analyze_program(blob: &ProgramBlob) -> Result<ProgramAnalysis, &'static str> {
+    let mut instructions = Vec::new();
+    let mut iter = blob.instructions();
+    loop {
+        let offset = iter.offset();
+        match iter.next() {
+            Some(Ok(instruction)) => instructions.push(AnalyzedInstruction {
+                offset,
+                length: iter.offset() - offset,
+                instruction,
+            }),
+            // Analysis covers the code up to the first malformed instruction
+            Some(Err(_)) | None => break,
+        }
+    }
+
+    let exports = blob
+        .exports()
+        .filter_map(Result::ok)
+        .map(|export| (export.symbol().to_string(), export.jump_target()))
+        .collect::<Vec<_>>();
+
+    let jump_table = blob.jump_table().collect::<Vec<_>>();
+
+    let mut analysis = analyze_instructions(instructions, &exports, &jump_table);
+    analysis.code_offset = subslice_range(blob, blob.code())
+        .ok_or("Code section is not part of the blob")?
+        .start;
+
+    Ok(analysis)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Instruction as I;
+
+    fn analyze(instructions: &[Instruction], exports: &[(&str, u32)]) -> ProgramAnalysis {
+        let instructions = instructions
+            .iter()
+            .enumerate()
+            .map(|(offset, &instruction)| AnalyzedInstruction { offset, length: 1, instruction })
+            .collect();
+        let exports = exports.iter().map(|&(name, target)| (name.to_string(), target)).collect::<Vec<_>>();
+        analyze_instructions(instructions, &exports, &[])
+    }
+
+    #[test]
+    fn splits_blocks_after_control_flow() {
+        let analysis = analyze(
+            &[
+                I::load_imm(Reg::A0, 1),
+                I::branch_eq_imm(Reg::A0, 0, 2),
+                I::jump(0),
+                I::trap,
+                I::load_imm(Reg::A1, 2),
+            ],
+            &[],
+        );
+
+        let ranges = analysis.blocks.iter().map(|block| block.instructions.clone()).collect::<Vec<_>>();
+        assert_eq!(ranges, [0..2, 2..3, 3..4, 4..5]);
+        assert_eq!(
+            analysis.blocks[0].successors,
+            [Edge { target: 2, kind: EdgeKind::Taken }, Edge { target: 1, kind: EdgeKind::NotTaken }]
+        );
+        assert_eq!(analysis.blocks[1].successors, [Edge { target: 0, kind: EdgeKind::Jump }]);
+        assert!(analysis.blocks[2].successors.is_empty());
+        // The trailing fallthrough has no block to go to
+        assert!(analysis.blocks[3].successors.is_empty());
+        assert_eq!(analysis.block_of_instruction(1).map(|block| block.jump_target), Some(0));
+        assert_eq!(analysis.block_of_instruction(5), None);
+    }
+
+    #[test]
+    fn finds_functions_from_calls_and_exports() {
+        let analysis = analyze(
+            &[
+                I::call(Reg::RA, 2),
+                I::jump_indirect(Reg::RA, 0),
+                I::load_imm(Reg::A0, 0),
+                // Tail jump into `main`, which stays out of `sub_2`
+                I::jump(0),
+                I::trap,
+            ],
+            &[("main", 0)],
+        );
+
+        let functions = analysis
+            .functions
+            .iter()
+            .map(|function| (function.name.as_str(), function.blocks.iter().copied().collect::<Vec<_>>()))
+            .collect::<Vec<_>>();
+        assert_eq!(functions, [("main", vec![0, 1]), ("sub_2", vec![2]), ("sub_3", vec![3])]);
+        assert_eq!(analysis.function_of_block(1).map(|function| function.name.as_str()), Some("main"));
+    }
+}
@@ -0,0 +1,8 @@
+//! Blob parsing, disassembly and program analysis shared by the web app and
+//! the `polkarun` command-line tool. Nothing in here depends on a UI.
+
+pub mod analysis;
+pub mod listing;
+pub mod sections;
+pub mod size;
+pub mod symbols;
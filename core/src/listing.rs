@@ -0,0 +1,282 @@
+use crate::analysis::{analyze_program, ProgramAnalysis};
+use crate::sections::subslice_range;
+use crate::symbols::program_symbols;
+use polkavm_common::program::{Instruction, ProgramBlob};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum OffsetBase {
+    #[default]
+    Hexadecimal,
+    Decimal,
+}
+
+/// How offsets, bytes and immediates are written in the hex dump and the
+/// disassembly listing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ListingFormat {
+    /// Bytes per row of the hex dump
+    pub bytes_per_row: usize,
+    pub uppercase_hex: bool,
+    pub offset_base: OffsetBase,
+    /// Digits offsets are zero padded to
+    pub offset_width: usize,
+    /// Show 32-bit immediates with the top bit set as negative numbers
+    pub signed_immediates: bool,
+}
+
+impl Default for ListingFormat {
+    fn default() -> Self {
+        Self {
+            bytes_per_row: 16,
+            uppercase_hex: true,
+            offset_base: OffsetBase::Hexadecimal,
+            offset_width: 6,
+            signed_immediates: false,
+        }
+    }
+}
+
+impl ListingFormat {
+    pub fn offset(&self, offset: usize) -> String {
+        let width = self.offset_width;
+        match (self.offset_base, self.uppercase_hex) {
+            (OffsetBase::Hexadecimal, true) => format!("{:0width$X}", offset),
+            (OffsetBase::Hexadecimal, false) => format!("{:0width$x}", offset),
+            (OffsetBase::Decimal, _) => format!("{:0width$}", offset),
+        }
+    }
+
+    pub fn byte(&self, byte: u8) -> String {
+        if self.uppercase_hex {
+            format!("{:02X}", byte)
+        } else {
+            format!("{:02x}", byte)
+        }
+    }
+
+    /// Space separated hex bytes
+    pub fn bytes(&self, bytes: &[u8]) -> String {
+        bytes.iter().map(|&byte| self.byte(byte)).collect::<Vec<_>>().join(" ")
+    }
+
+    pub fn assembly(&self, assembly: &str) -> String {
+        if self.signed_immediates {
+            signed_numbers(assembly)
+        } else {
+            assembly.to_string()
+        }
+    }
+}
+
+// A 32-bit number with the top bit set as a negative one, keeping its base
+fn signed_number(token: &str) -> Option<String> {
+    let (value, is_hex) = match token.strip_prefix("0x") {
+        Some(digits) => (u32::from_str_radix(digits, 16).ok()?, true),
+        None => (token.parse::<u32>().ok()?, false),
+    };
+    let signed = value as i32;
+    if signed >= 0 {
+        None
+    } else if is_hex {
+        Some(format!("-0x{:x}", signed.unsigned_abs()))
+    } else {
+        Some(signed.to_string())
+    }
+}
+
+// Rewrites the numbers in an instruction's text, e.g. `4294967292` as `-4`;
+// register names and mnemonics are left alone
+fn signed_numbers(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(|c: char| c.is_ascii_alphanumeric()) {
+        result.push_str(&rest[..start]);
+        let length = rest[start..]
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .unwrap_or(rest.len() - start);
+        let token = &rest[start..start + length];
+        match signed_number(token) {
+            Some(signed) => result.push_str(&signed),
+            None => result.push_str(token),
+        }
+        rest = &rest[start + length..];
+    }
+    result.push_str(rest);
+    result
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DisassembledLine {
+    pub offset: String,
+    /// Byte offset within the blob
+    pub address: usize,
+    /// Encoded instruction; empty for lines reporting a parse error
+    #[serde(skip)]
+    pub bytes: Vec<u8>,
+    pub hex: String,
+    pub assembly: String,
+    pub operation: String,
+    /// Set on the first instruction of each basic block
+    pub jump_target: Option<u32>,
+    pub comment: Option<String>,
+}
+
+// Helper function to create a new DisassembledLine
+impl DisassembledLine {
+    pub fn new(offset: usize, hex: String, assembly: String, operation: String) -> Self {
+        Self {
+            offset: format!("{:06X}", offset),
+            address: offset,
+            bytes: Vec::new(),
+            hex,
+            assembly,
+            operation,
+            jump_target: None,
+            comment: None,
+        }
+    }
+
+    /// Bytes of the blob the instruction occupies; empty for errors
+    pub fn byte_range(&self) -> std::ops::Range<usize> {
+        self.address..self.address + self.bytes.len()
+    }
+}
+
+/// Hex dump rows of `format.bytes_per_row` bytes: offset, hex bytes and
+/// printable ASCII.
+pub fn unified_representation(data: &[u8], format: &ListingFormat) -> Vec<String> {
+    let chunk_size = format.bytes_per_row.max(1);
+    let hex_length = chunk_size * 3 - 1; // Expected length of the hex part
+
+    data.chunks(chunk_size)
+        .enumerate()
+        .map(move |(index, chunk)| {
+            let current_offset = index * chunk_size; // Calculate offset here
+
+            // Initialize hex_part and text_part
+            let mut hex_part = String::with_capacity(hex_length);
+            let mut text_part = String::with_capacity(chunk_size);
+
+            for &byte in chunk {
+                // Append to hex_part and text_part
+                hex_part.push_str(&format.byte(byte));
+                hex_part.push(' ');
+                text_part.push(if (32..=126).contains(&byte) { byte as char } else { '.' });
+            }
+
+            // Pad the hex_part and text_part if necessary
+            while hex_part.len() < hex_length {
+                hex_part.push_str("..");
+                text_part.push(' ');
+            }
+
+            // Ensure hex_part and text_part are of consistent length
+            let hex_part_padded = format!("{:width$}", hex_part, width = hex_length);
+            let text_part_padded = format!("{:<width$}", text_part, width = chunk_size);
+
+            // Format the output string with the current offset
+            format!("{} {} {}", format.offset(current_offset), hex_part_padded, text_part_padded)
+        })
+        .collect()
+}
+
+/// One line per instruction, in code order, with block starts and import
+/// names filled in.
+pub fn disassemble_into_lines(data: &[u8]) -> Result<(Vec<DisassembledLine>, ProgramAnalysis), &'static str> {
+    let blob = ProgramBlob::parse(data).map_err(|_| "Failed to parse blob")?;
+
+    let code_offset = subslice_range(&blob, blob.code())
+        .ok_or("Failed to locate the code section")?
+        .start;
+
+    let symbols = program_symbols(&blob)?;
+
+    let analysis = analyze_program(&blob)?;
+
+    let mut result = Vec::new();
+    let mut instructions = blob.instructions();
+
+    loop {
+        // Offset within the code section as reported by the parser, before
+        // the instruction is decoded
+        let instruction_offset = instructions.offset();
+        let Some(maybe_instruction) = instructions.next() else {
+            break;
+        };
+
+        match maybe_instruction {
+            Ok(instruction) => {
+                let size = instructions.offset() - instruction_offset;
+                let bytes = &blob.code()[instruction_offset..instruction_offset + size];
+
+                // Extract the opcode name from the instruction
+                let opcode_name = format!("{:?}", instruction.opcode());
+
+                let mut line = DisassembledLine::new(
+                    code_offset + instruction_offset,
+                    ListingFormat::default().bytes(bytes),
+                    instruction.to_string(),
+                    opcode_name, // Pass the opcode name to the operation field
+                );
+                line.bytes = bytes.to_vec();
+
+                if let Instruction::ecalli(import_index) = instruction {
+                    line.comment = Some(
+                        symbols
+                            .import_name(import_index)
+                            .map_or_else(|| format!("unknown import #{}", import_index), str::to_string),
+                    );
+                }
+
+                result.push(line);
+            },
+            Err(error) => {
+                result.push(DisassembledLine::new(
+                    code_offset + instruction_offset,
+                    "ERROR".to_string(),
+                    format!("Error: {}", error),
+                    "Unknown".to_string(), // Use a placeholder like "Unknown" for errors
+                ));
+
+                // The parser can't resynchronize after a malformed instruction,
+                // so don't report the rest of the section at a bogus offset
+                if instructions.offset() == instruction_offset {
+                    break;
+                }
+            }
+        }
+    }
+
+    for block in &analysis.blocks {
+        if let Some(line) = result.get_mut(block.instructions.start) {
+            line.jump_target = Some(block.jump_target);
+        }
+    }
+
+    Ok((result, analysis))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pads_the_last_hex_dump_row() {
+        let format = ListingFormat { bytes_per_row: 2, ..ListingFormat::default() };
+        assert_eq!(unified_representation(b"AB\x00", &format), ["000000 41 42  AB", "000002 00 .. . "]);
+    }
+
+    #[test]
+    fn formats_hex_dump_offsets_and_case() {
+        let format = ListingFormat {
+            bytes_per_row: 1,
+            uppercase_hex: false,
+            offset_base: OffsetBase::Decimal,
+            offset_width: 3,
+            ..ListingFormat::default()
+        };
+        assert_eq!(unified_representation(&[0xab; 11], &format)[10], "010 ab  .");
+    }
+}
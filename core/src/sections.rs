@@ -0,0 +1,196 @@
+use polkavm_common::program::{
+    ProgramBlob, BLOB_MAGIC, SECTION_CODE, SECTION_END_OF_FILE, SECTION_EXPORTS, SECTION_IMPORTS,
+    SECTION_JUMP_TABLE, SECTION_MEMORY_CONFIG, SECTION_OPT_DEBUG_LINE_PROGRAMS,
+    SECTION_OPT_DEBUG_LINE_PROGRAM_RANGES, SECTION_OPT_DEBUG_STRINGS, SECTION_RO_DATA,
+    SECTION_RW_DATA,
+};
+use serde::Serialize;
+use std::ops::Range;
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct BlobSection {
+    pub name: String,
+    pub range: Range<usize>,
+    pub contents: Vec<String>,
+}
+
+impl BlobSection {
+    fn new(name: &str, range: Range<usize>) -> Self {
+        Self {
+            name: name.to_string(),
+            range,
+            contents: Vec::new(),
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.range.len()
+    }
+}
+
+/// Reads a length the way polkavm and the JAM PVM encode them: the number of
+/// leading one bits in the first byte is the number of extra little-endian
+/// bytes.
+pub fn read_varint(data: &[u8], position: &mut usize) -> Option<u32> {
+    let first_byte = *data.get(*position)?;
+    let length = first_byte.leading_ones() as usize;
+    if length > 4 {
+        return None;
+    }
+
+    let bytes = data.get(*position + 1..*position + 1 + length)?;
+    let mut value = 0u32;
+    for (index, &byte) in bytes.iter().enumerate() {
+        value |= u32::from(byte) << (index * 8);
+    }
+
+    if length < 4 {
+        let upper_bits = u32::from(first_byte) & (0xff >> (length + 1));
+        value |= upper_bits << (length * 8);
+    }
+
+    *position += 1 + length;
+    Some(value)
+}
+
+fn section_name(section: u8) -> &'static str {
+    match section {
+        SECTION_MEMORY_CONFIG => "Memory config",
+        SECTION_RO_DATA => "ro_data",
+        SECTION_RW_DATA => "rw_data",
+        SECTION_IMPORTS => "Imports",
+        SECTION_EXPORTS => "Exports",
+        SECTION_CODE => "Code",
+        SECTION_JUMP_TABLE => "Jump table",
+        SECTION_OPT_DEBUG_STRINGS => "Debug strings",
+        SECTION_OPT_DEBUG_LINE_PROGRAMS => "Debug line programs",
+        SECTION_OPT_DEBUG_LINE_PROGRAM_RANGES => "Debug line program ranges",
+        _ => "Unknown",
+    }
+}
+
+// Byte range of a slice handed out by `ProgramBlob` within the raw blob
+pub fn subslice_range(blob: &ProgramBlob, slice: &[u8]) -> Option<Range<usize>> {
+    let blob_start = blob.as_bytes().as_ptr() as usize;
+    let slice_start = slice.as_ptr() as usize;
+    if slice_start < blob_start || slice_start + slice.len() > blob_start + blob.as_bytes().len() {
+        return None;
+    }
+
+    let start = slice_start - blob_start;
+    Some(start..start + slice.len())
+}
+
+fn section_contents(blob: &ProgramBlob, section: u8) -> Vec<String> {
+    match section {
+        SECTION_MEMORY_CONFIG => {
+            let bss_size = (blob.rw_data_size() as usize).saturating_sub(blob.rw_data().len());
+            vec![
+                format!("ro_data size: {} bytes", blob.ro_data_size()),
+                format!("rw_data size: {} bytes", blob.rw_data_size()),
+                format!("bss size: {} bytes", bss_size),
+                format!("stack size: {} bytes", blob.stack_size()),
+            ]
+        }
+        SECTION_RO_DATA => vec![format!("{} initialized bytes", blob.ro_data().len())],
+        SECTION_RW_DATA => vec![format!("{} initialized bytes", blob.rw_data().len())],
+        SECTION_IMPORTS => blob
+            .imports()
+            .enumerate()
+            .map(|(index, import)| match import {
+                Ok(import) => format!("#{}: {}", index, import.symbol()),
+                Err(error) => format!("#{}: Error: {}", index, error),
+            })
+            .collect(),
+        SECTION_EXPORTS => blob
+            .exports()
+            .map(|export| match export {
+                Ok(export) => format!("{} @ {}", export.symbol(), export.jump_target()),
+                Err(error) => format!("Error: {}", error),
+            })
+            .collect(),
+        SECTION_CODE => {
+            let instruction_count = blob.instructions().filter(Result::is_ok).count();
+            vec![
+                format!("{} instructions", instruction_count),
+                format!("{} code bytes", blob.code().len()),
+            ]
+        }
+        SECTION_JUMP_TABLE => blob
+            .jump_table()
+            .enumerate()
+            .map(|(index, target)| format!("[{}] -> @{}", index, target))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+pub fn blob_sections(data: &[u8]) -> Result<Vec<BlobSection>, &'static str> {
+    let blob = ProgramBlob::parse(data).map_err(|_| "Failed to parse blob")?;
+
+    if !data.starts_with(&BLOB_MAGIC) {
+        return Err("Missing blob magic");
+    }
+
+    // Magic plus the version byte
+    let header_size = BLOB_MAGIC.len() + 1;
+    let mut header = BlobSection::new("Header", 0..header_size);
+    header.contents.push(format!("version: {}", data[BLOB_MAGIC.len()]));
+    let mut result = vec![header];
+
+    let mut position = header_size;
+    while let Some(&section) = data.get(position) {
+        let section_start = position;
+        position += 1;
+        if section == SECTION_END_OF_FILE {
+            result.push(BlobSection::new("End of file", section_start..position));
+            break;
+        }
+
+        let length = read_varint(data, &mut position).ok_or("Failed to read section length")? as usize;
+        let section_end = position.checked_add(length).filter(|&end| end <= data.len())
+            .ok_or("Section extends past the end of the blob")?;
+
+        let mut blob_section = BlobSection::new(section_name(section), section_start..section_end);
+        blob_section.contents = section_contents(&blob, section);
+        result.push(blob_section);
+
+        position = section_end;
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &[u8] = include_bytes!("../../app/public/samples/example-hello-world.polkavm");
+
+    #[test]
+    fn reads_varints() {
+        let read = |data: &[u8]| {
+            let mut position = 0;
+            read_varint(data, &mut position).map(|value| (value, position))
+        };
+        assert_eq!(read(&[0x7f]), Some((0x7f, 1)));
+        assert_eq!(read(&[0x92, 0x34]), Some((0x1234, 2)));
+        assert_eq!(read(&[0xf0, 0x78, 0x56, 0x34, 0x12]), Some((0x12345678, 5)));
+        assert_eq!(read(&[0x92]), None);
+        assert_eq!(read(&[0xf8, 0, 0, 0, 0, 0]), None);
+    }
+
+    #[test]
+    fn splits_the_blob_into_contiguous_sections() {
+        let sections = blob_sections(SAMPLE).unwrap();
+        assert_eq!(sections.first().map(|section| section.name.as_str()), Some("Header"));
+        assert_eq!(sections.last().map(|section| section.name.as_str()), Some("End of file"));
+        assert!(sections.iter().any(|section| section.name == "Code"));
+        for pair in sections.windows(2) {
+            assert_eq!(pair[0].range.end, pair[1].range.start);
+        }
+        assert_eq!(sections.last().unwrap().range.end, SAMPLE.len());
+
+        assert!(blob_sections(&SAMPLE[..3]).is_err());
+    }
+}
@@ -0,0 +1,386 @@
+use crate::analysis::{analyze_program, control_flow, BasicBlock, ControlFlow, Function, ProgramAnalysis};
+use crate::sections::blob_sections;
+use crate::symbols::program_symbols;
+use polkavm_common::program::ProgramBlob;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SizeEntry {
+    pub name: String,
+    /// Bytes
+    pub size: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportSize {
+    pub name: String,
+    /// Code bytes of the function the export enters
+    pub size: usize,
+    /// Code bytes reachable from the export through jumps, branches and direct
+    /// calls. Targets of indirect jumps and calls aren't known, so they are not
+    /// followed.
+    pub retained: usize,
+}
+
+/// Where the bytes of a blob go, largest first within each group.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SizeReport {
+    pub total: usize,
+    pub sections: Vec<SizeEntry>,
+    pub functions: Vec<SizeEntry>,
+    pub exports: Vec<ExportSize>,
+}
+
+fn block_size(analysis: &ProgramAnalysis, block: &BasicBlock) -> usize {
+    analysis
+        .instructions
+        .get(block.instructions.clone())
+        .unwrap_or_default()
+        .iter()
+        .map(|analyzed| analyzed.length)
+        .sum()
+}
+
+fn function_size(analysis: &ProgramAnalysis, function: &Function) -> usize {
+    function
+        .blocks
+        .iter()
+        .filter_map(|&jump_target| analysis.block(jump_target))
+        .map(|block| block_size(analysis, block))
+        .sum()
+}
+
+fn retained_size(analysis: &ProgramAnalysis, entry: u32) -> usize {
+    let mut seen = BTreeSet::new();
+    let mut pending = vec![entry];
+    while let Some(jump_target) = pending.pop() {
+        let Some(block) = analysis.block(jump_target) else {
+            continue;
+        };
+        if !seen.insert(jump_target) {
+            continue;
+        }
+
+        pending.extend(block.successors.iter().map(|edge| edge.target));
+        let last = block.instructions.end.checked_sub(1).and_then(|index| analysis.instructions.get(index));
+        if let Some(ControlFlow::Call(target)) = last.map(|analyzed| control_flow(&analyzed.instruction)) {
+            pending.push(target);
+        }
+    }
+
+    seen.iter()
+        .filter_map(|&jump_target| analysis.block(jump_target))
+        .map(|block| block_size(analysis, block))
+        .sum()
+}
+
+fn sorted(sizes: BTreeMap<String, usize>) -> Vec<SizeEntry> {
+    let mut entries = sizes
+        .into_iter()
+        .map(|(name, size)| SizeEntry { name, size })
+        .collect::<Vec<_>>();
+    entries.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+    entries
+}
+
+pub fn size_report(data: &[u8]) -> Result<SizeReport, &'static str> {
+    let blob = ProgramBlob::parse(data).map_err(|_| "Failed to parse blob")?;
+    let analysis = analyze_program(&blob)?;
+    let symbols = program_symbols(&blob)?;
+
+    let mut sections = BTreeMap::new();
+    for section in blob_sections(data)? {
+        *sections.entry(section.name).or_default() += section.size();
+    }
+
+    // Several exports may share a symbol; the later ones are told apart by
+    // their entry block
+    let mut functions = BTreeMap::new();
+    for function in &analysis.functions {
+        let name = if functions.contains_key(&function.name) {
+            format!("{}@{}", function.name, function.entry)
+        } else {
+            function.name.clone()
+        };
+        functions.insert(name, function_size(&analysis, function));
+    }
+
+    let mut exports = symbols
+        .exports
+        .iter()
+        .map(|export| ExportSize {
+            name: export.name.clone(),
+            size: analysis
+                .function_of_block(export.jump_target)
+                .map_or(0, |function| function_size(&analysis, function)),
+            retained: retained_size(&analysis, export.jump_target),
+        })
+        .collect::<Vec<_>>();
+    exports.sort_by(|a, b| b.retained.cmp(&a.retained).then_with(|| a.name.cmp(&b.name)));
+
+    Ok(SizeReport {
+        total: data.len(),
+        sections: sorted(sections),
+        functions: sorted(functions),
+        exports,
+    })
+}
+
+/// Row the baseline comparison sums unnamed functions into
+pub const OTHER_FUNCTIONS: &str = "other functions";
+
+/// Functions without an export are named `sub_N` after their entry block, so
+/// their names shift whenever a block is inserted before them.
+fn is_unnamed(name: &str) -> bool {
+    name.strip_prefix("sub_")
+        .is_some_and(|index| !index.is_empty() && index.bytes().all(|byte| byte.is_ascii_digit()))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SizeCategory {
+    Total,
+    Section,
+    Function,
+    /// Compares retained sizes
+    Export,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct SizeDelta {
+    pub category: SizeCategory,
+    pub name: String,
+    /// `None` where the entry only exists on one side
+    pub baseline: Option<usize>,
+    pub current: Option<usize>,
+}
+
+impl SizeDelta {
+    pub fn delta(&self) -> i64 {
+        self.current.unwrap_or(0) as i64 - self.baseline.unwrap_or(0) as i64
+    }
+}
+
+fn entry_deltas(
+    category: SizeCategory,
+    baseline: impl Iterator<Item = (String, usize)>,
+    current: impl Iterator<Item = (String, usize)>,
+) -> Vec<SizeDelta> {
+    let mut pairs = BTreeMap::<String, (Option<usize>, Option<usize>)>::new();
+    for (name, size) in baseline {
+        *pairs.entry(name).or_default().0.get_or_insert(0) += size;
+    }
+    for (name, size) in current {
+        *pairs.entry(name).or_default().1.get_or_insert(0) += size;
+    }
+
+    let mut deltas = pairs
+        .into_iter()
+        .filter(|(_, (baseline, current))| baseline != current)
+        .map(|(name, (baseline, current))| SizeDelta {
+            category,
+            name,
+            baseline,
+            current,
+        })
+        .collect::<Vec<_>>();
+    deltas.sort_by(|a, b| b.delta().abs().cmp(&a.delta().abs()).then_with(|| a.name.cmp(&b.name)));
+    deltas
+}
+
+/// Entries whose size changed between two reports, biggest change first
+/// within each category. The total is always included. Unnamed functions
+/// are compared as one `OTHER_FUNCTIONS` row, as their names don't carry
+/// over between builds.
+pub fn compare_sizes(baseline: &SizeReport, current: &SizeReport) -> Vec<SizeDelta> {
+    let sections = |report: &SizeReport| report.sections.clone().into_iter().map(|entry| (entry.name, entry.size));
+    let functions = |report: &SizeReport| {
+        report.functions.clone().into_iter().map(|entry| {
+            let name = if is_unnamed(&entry.name) { OTHER_FUNCTIONS.to_string() } else { entry.name };
+            (name, entry.size)
+        })
+    };
+    let exports = |report: &SizeReport| report.exports.clone().into_iter().map(|export| (export.name, export.retained));
+
+    let mut deltas = vec![SizeDelta {
+        category: SizeCategory::Total,
+        name: "Total".to_string(),
+        baseline: Some(baseline.total),
+        current: Some(current.total),
+    }];
+    deltas.extend(entry_deltas(SizeCategory::Section, sections(baseline), sections(current)));
+    deltas.extend(entry_deltas(SizeCategory::Function, functions(baseline), functions(current)));
+    deltas.extend(entry_deltas(SizeCategory::Export, exports(baseline), exports(current)));
+    deltas
+}
+
+/// Size limits for CI, in bytes, e.g.
+///
+/// ```toml
+/// total = 65536
+/// growth = 512
+///
+/// [sections]
+/// Code = 40000
+///
+/// [functions]
+/// main = 4096
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Budget {
+    pub total: Option<usize>,
+    /// Largest allowed growth of the total over the baseline
+    pub growth: Option<i64>,
+    /// Keyed by section name as shown in the size report
+    #[serde(default)]
+    pub sections: BTreeMap<String, usize>,
+    #[serde(default)]
+    pub functions: BTreeMap<String, usize>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct BudgetViolation {
+    pub what: String,
+    pub limit: i64,
+    pub actual: i64,
+}
+
+impl Budget {
+    pub fn parse(filename: &str, contents: &str) -> Result<Self, String> {
+        if filename.ends_with(".toml") {
+            toml::from_str(contents).map_err(|error| format!("Invalid budget: {}", error))
+        } else {
+            ron::from_str(contents).map_err(|error| format!("Invalid budget: {}", error))
+        }
+    }
+
+    /// Every limit `report` exceeds. A growth limit needs a baseline.
+    pub fn check(&self, report: &SizeReport, baseline: Option<&SizeReport>) -> Result<Vec<BudgetViolation>, String> {
+        let mut violations = Vec::new();
+        let mut check = |what: String, limit: i64, actual: i64| {
+            if actual > limit {
+                violations.push(BudgetViolation { what, limit, actual });
+            }
+        };
+
+        if let Some(total) = self.total {
+            check("total size".to_string(), total as i64, report.total as i64);
+        }
+        if let Some(growth) = self.growth {
+            let baseline = baseline.ok_or("A growth budget needs a baseline")?;
+            check("growth over baseline".to_string(), growth, report.total as i64 - baseline.total as i64);
+        }
+
+        let size_of = |entries: &[SizeEntry], name: &str| entries.iter().find(|entry| entry.name == name).map_or(0, |entry| entry.size);
+        for (name, &limit) in &self.sections {
+            check(format!("section {}", name), limit as i64, size_of(&report.sections, name) as i64);
+        }
+        for (name, &limit) in &self.functions {
+            check(format!("fn {}", name), limit as i64, size_of(&report.functions, name) as i64);
+        }
+
+        Ok(violations)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(sizes: &[(&str, usize)]) -> Vec<SizeEntry> {
+        sizes.iter().map(|&(name, size)| SizeEntry { name: name.to_string(), size }).collect()
+    }
+
+    fn report(total: usize) -> SizeReport {
+        SizeReport {
+            total,
+            sections: entries(&[("Code", 600), ("ro_data", 100)]),
+            functions: entries(&[("main", 400), ("sub_3", 200)]),
+            exports: Vec::new(),
+        }
+    }
+
+    fn violation(what: &str, limit: i64, actual: i64) -> BudgetViolation {
+        BudgetViolation { what: what.to_string(), limit, actual }
+    }
+
+    #[test]
+    fn reports_each_exceeded_limit() {
+        let budget = Budget::parse(
+            "budget.toml",
+            r#"
+                total = 800
+                [sections]
+                Code = 500
+                ro_data = 100
+                [functions]
+                main = 300
+                sub_3 = 300
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            budget.check(&report(1000), None),
+            Ok(vec![
+                violation("total size", 800, 1000),
+                violation("section Code", 500, 600),
+                violation("fn main", 300, 400),
+            ])
+        );
+        assert_eq!(budget.check(&report(800), None).map(|violations| violations.len()), Ok(2));
+    }
+
+    #[test]
+    fn growth_is_measured_against_the_baseline() {
+        let budget = Budget { growth: Some(100), ..Budget::default() };
+
+        assert_eq!(budget.check(&report(1100), Some(&report(1000))), Ok(Vec::new()));
+        assert_eq!(
+            budget.check(&report(1200), Some(&report(1000))),
+            Ok(vec![violation("growth over baseline", 100, 200)])
+        );
+        assert_eq!(budget.check(&report(1200), None), Err("A growth budget needs a baseline".to_string()));
+    }
+
+    #[test]
+    fn deltas_leave_out_unchanged_entries() {
+        let sizes = |sizes: &[(&str, usize)]| sizes.iter().map(|&(name, size)| (name.to_string(), size)).collect::<Vec<_>>();
+        let deltas = entry_deltas(
+            SizeCategory::Function,
+            sizes(&[("main", 400), ("same", 50), ("removed", 30)]).into_iter(),
+            sizes(&[("main", 420), ("same", 50), ("added", 60)]).into_iter(),
+        );
+
+        let changes = deltas
+            .iter()
+            .map(|delta| (delta.name.as_str(), delta.baseline, delta.current))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            [("added", None, Some(60)), ("removed", Some(30), None), ("main", Some(400), Some(420))]
+        );
+    }
+
+    #[test]
+    fn shifted_unnamed_functions_are_compared_together() {
+        let functions = |sizes: &[(&str, usize)]| SizeReport {
+            total: 1000,
+            functions: entries(sizes),
+            ..SizeReport::default()
+        };
+        let baseline = functions(&[("main", 400), ("sub_3", 200), ("sub_7", 50)]);
+
+        let shifted = functions(&[("main", 400), ("sub_4", 200), ("sub_8", 50)]);
+        assert_eq!(compare_sizes(&baseline, &shifted).len(), 1);
+
+        let grown = functions(&[("main", 400), ("sub_4", 200), ("sub_8", 60)]);
+        let changes = compare_sizes(&baseline, &grown)
+            .into_iter()
+            .filter(|delta| delta.category == SizeCategory::Function)
+            .map(|delta| (delta.name, delta.baseline, delta.current))
+            .collect::<Vec<_>>();
+        assert_eq!(changes, [(OTHER_FUNCTIONS.to_string(), Some(250), Some(260))]);
+    }
+}
@@ -0,0 +1,63 @@
+use polkavm_common::program::ProgramBlob;
+use serde::Serialize;
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ExportSymbol {
+    pub name: String,
+    pub jump_target: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ImportSymbol {
+    pub index: u32,
+    pub name: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct ProgramSymbols {
+    pub exports: Vec<ExportSymbol>,
+    pub imports: Vec<ImportSymbol>,
+}
+
+impl ProgramSymbols {
+    pub fn is_empty(&self) -> bool {
+        self.exports.is_empty() && self.imports.is_empty()
+    }
+
+    pub fn import_name(&self, index: u32) -> Option<&str> {
+        self.imports
+            .iter()
+            .find(|import| import.index == index)
+            .map(|import| import.name.as_str())
+    }
+}
+
+pub fn program_symbols(blob: &ProgramBlob) -> Result<ProgramSymbols, &'static str> {
+    let exports = blob
+        .exports()
+        .map(|export| {
+            export
+                .map(|export| ExportSymbol {
+                    name: export.symbol().to_string(),
+                    jump_target: export.jump_target(),
+                })
+                .map_err(|_| "Failed to parse exports")
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // `ecalli` refers to imports by their position in the import section
+    let imports = blob
+        .imports()
+        .enumerate()
+        .map(|(index, import)| {
+            import
+                .map(|import| ImportSymbol {
+                    index: index as u32,
+                    name: import.symbol().to_string(),
+                })
+                .map_err(|_| "Failed to parse imports")
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(ProgramSymbols { exports, imports })
+}